@@ -19,8 +19,9 @@
 mode=/data/adb/modules/EfficientScheduler/mode
 
 case "$1" in
-"init" | "fast" | "pedestal") echo fast >$mode ;;
+"fast" | "pedestal") echo fast >$mode ;;
 "powersave" | "standby") echo powersave >$mode ;;
 "balance") echo balance >$mode ;;
 "performance") echo performance >$mode ;;
+"init" | "auto") echo auto >$mode ;;
 esac
@@ -0,0 +1,299 @@
+// Copyright 2023-2025, [rust@localhost] $ (@3532340532)
+//
+// This file is part of EfficientScheduler.
+//
+// EfficientScheduler is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// EfficientScheduler is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along
+// with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Result, bail};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde_json::Value;
+
+use crate::framework::{
+    control::{Request, client::Client, default_socket},
+    kernel,
+    scheduler::manual::Until,
+};
+
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// 配置文件路径，未指定时自动查找
+    #[arg(short, long, env = "EFFICIENT_SCHEDULER_CONFIG")]
+    pub config: Option<PathBuf>,
+    /// 内核节点的根目录，用于在模拟的目录树上运行
+    #[arg(long, env = "EFFICIENT_SCHEDULER_ROOT")]
+    pub root: Option<PathBuf>,
+    /// 只记录将要进行的写入、权限修改与挂载，不实际执行，也不结束其他调度进程
+    #[arg(long)]
+    pub dry_run: bool,
+    /// 日志同时输出到标准输出，便于前台调试
+    #[arg(long)]
+    pub stdout: bool,
+    /// 从目录中的 `window` 与 `power` 读取事先保存的 dumpsys 输出，代替实时获取，
+    /// 可为单个文件或按文件名顺序回放的目录
+    #[arg(long, value_name = "DIR")]
+    pub replay: Option<PathBuf>,
+    /// 控制接口的套接字路径，默认位于模块目录
+    #[arg(long, global = true, env = "EFFICIENT_SCHEDULER_SOCKET")]
+    pub socket: Option<PathBuf>,
+}
+
+impl Cli {
+    pub fn socket(&self) -> PathBuf {
+        self.socket.clone().unwrap_or_else(default_socket)
+    }
+}
+
+/// 除 `restore` 外均需要守护进程正在运行
+#[derive(Subcommand)]
+pub enum Command {
+    /// 查看当前模式、原因与系统状态
+    Status {
+        /// 输出原始 JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// 手动覆盖模式，`auto` 表示清除覆盖并交由规则决定
+    Mode {
+        mode: String,
+        /// 覆盖的持续时间，如 `30s`、`10m`、`1h`，未指定时一直有效
+        #[arg(long = "for", value_parser = humantime::parse_duration)]
+        duration: Option<Duration>,
+        /// 满足条件时提前结束覆盖
+        #[arg(long, value_enum)]
+        until: Option<UntilArg>,
+    },
+    /// 重新加载配置文件
+    Reload,
+    /// 说明当前模式是由哪条规则决定的
+    Explain {
+        /// 输出原始 JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// 还原所有被修改过的内核节点，守护进程未运行时按快照还原
+    Restore,
+}
+
+/// 命令行中的 [`Until`]，使调度器不依赖 clap
+#[derive(Clone, Copy, ValueEnum)]
+pub enum UntilArg {
+    /// 顶层应用不再是设置覆盖时的应用
+    TopappChange,
+    /// 屏幕熄灭，设置时已息屏则等到下一次亮屏后再熄灭
+    ScreenOff,
+}
+
+impl From<UntilArg> for Until {
+    fn from(until: UntilArg) -> Self {
+        match until {
+            UntilArg::TopappChange => Self::TopappChange,
+            UntilArg::ScreenOff => Self::ScreenOff,
+        }
+    }
+}
+
+/// 把子命令转交给正在运行的守护进程
+pub fn request(socket: &Path, request: &Request) -> Result<Value> {
+    let response = Client::connect(socket)?.request(request)?;
+    if !response.ok {
+        bail!(response.error.unwrap_or_default());
+    }
+    Ok(response.data)
+}
+
+/// 守护进程正在运行时由它还原并退出，否则按快照还原
+pub fn restore(socket: &Path) -> Result<()> {
+    let Ok(mut client) = Client::connect(socket) else {
+        log::info!("守护进程未运行，按快照还原");
+        kernel::snapshot::restore()?;
+        return Ok(());
+    };
+    let response = client.request(&Request::Restore)?;
+    if !response.ok {
+        bail!(response.error.unwrap_or_default());
+    }
+    log::info!("守护进程已还原内核节点并退出");
+    Ok(())
+}
+
+pub fn run(socket: &Path, command: Command) -> Result<()> {
+    match command {
+        Command::Status { json } => {
+            let status = request(socket, &Request::Status)?;
+            if json {
+                println!("{status}");
+            } else {
+                print_status(&status);
+            }
+        }
+        Command::Mode {
+            mode,
+            duration,
+            until,
+        } => {
+            let status = if mode == "auto" {
+                request(socket, &Request::ClearOverride)?
+            } else {
+                request(
+                    socket,
+                    &Request::SetMode {
+                        mode,
+                        duration: duration.map(|duration| duration.as_secs()),
+                        until: until.map(Until::from),
+                    },
+                )?
+            };
+            print_status(&status);
+        }
+        Command::Reload => {
+            let status = request(socket, &Request::ReloadConfig)?;
+            print_status(&status);
+        }
+        Command::Explain { json } => {
+            let explanation = request(socket, &Request::Explain)?;
+            if json {
+                println!("{explanation}");
+            } else {
+                print_explanation(&explanation);
+            }
+        }
+        Command::Restore => restore(socket)?,
+    }
+    Ok(())
+}
+
+fn print_status(status: &Value) {
+    let text = |value: &Value| value.as_str().unwrap_or("无").to_string();
+    println!("模式：{}", text(&status["mode"]));
+    println!("原因：{}", text(&status["reason"]));
+    println!("顶层应用：{}", text(&status["topapp"]));
+    let windows: Vec<_> = status["windows"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(describe_window)
+        .collect();
+    if !windows.is_empty() {
+        println!("可见窗口：{}", windows.join("，"));
+    }
+    let screen = if status["screen_on"].as_bool().unwrap_or_default() {
+        "亮屏"
+    } else {
+        "息屏"
+    };
+    println!("屏幕：{screen}");
+    let manual = &status["manual"];
+    let manual = if manual.is_null() {
+        String::from("无")
+    } else {
+        let mut line = text(&manual["mode"]);
+        if let Some(left) = manual["remaining"].as_u64() {
+            let left = humantime::format_duration(Duration::from_secs(left));
+            line = format!("{line}，剩余{left}");
+        }
+        match manual["until"].as_str() {
+            Some("topapp-change") => line += "，直到顶层应用变化",
+            Some("screen-off") => line += "，直到息屏",
+            _ => {}
+        }
+        line
+    };
+    println!("手动覆盖：{manual}");
+    println!("Scene：{}", text(&status["scene"]));
+}
+
+/// 如 `com.tencent.mm（分屏，焦点）`，全屏的普通窗口只显示包名
+fn describe_window(window: &Value) -> String {
+    let mut tags = Vec::new();
+    match window["type"].as_str() {
+        Some("overlay") => tags.push(String::from("悬浮窗")),
+        Some("dialog") => tags.push(String::from("对话框")),
+        _ => {}
+    }
+    match window["mode"].as_str() {
+        Some("pinned") => tags.push(String::from("画中画")),
+        Some("freeform") => tags.push(String::from("小窗")),
+        Some("multi-window") => tags.push(String::from("分屏")),
+        _ => {}
+    }
+    if let Some(display) = window["display"].as_u64().filter(|display| *display != 0) {
+        tags.push(format!("屏幕{display}"));
+    }
+    if window["focused"].as_bool().unwrap_or_default() {
+        tags.push(String::from("焦点"));
+    }
+    let package = window["package"].as_str().unwrap_or_default();
+    if tags.is_empty() {
+        package.to_string()
+    } else {
+        format!("{package}（{}）", tags.join("，"))
+    }
+}
+
+fn print_explanation(explanation: &Value) {
+    let screen = if explanation["screen_on"].as_bool().unwrap_or_default() {
+        "亮屏"
+    } else {
+        "息屏"
+    };
+    println!(
+        "顶层应用：{}，{screen}",
+        explanation["topapp"].as_str().unwrap_or_default()
+    );
+    for step in explanation["steps"].as_array().into_iter().flatten() {
+        let marker = if step["hit"].as_bool().unwrap_or_default() {
+            "->"
+        } else {
+            "  "
+        };
+        println!(
+            "{marker} {}：{}",
+            step["rule"].as_str().unwrap_or_default(),
+            step["detail"].as_str().unwrap_or_default()
+        );
+    }
+    println!("当前模式：{}", explanation["mode"].as_str().unwrap_or("无"));
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn config_flag_and_env() {
+        // 修改环境变量会与其他线程中的测试竞争，只检查声明的绑定
+        let command = Cli::command();
+        let config = command
+            .get_arguments()
+            .find(|arg| arg.get_id() == "config")
+            .unwrap();
+        assert_eq!(
+            config.get_env(),
+            Some(std::ffi::OsStr::new("EFFICIENT_SCHEDULER_CONFIG"))
+        );
+
+        let cli = Cli::try_parse_from(["es", "--config", "/cli/config.toml"]).unwrap();
+        assert_eq!(cli.config, Some(PathBuf::from("/cli/config.toml")));
+    }
+}
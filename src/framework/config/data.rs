@@ -15,13 +15,400 @@
 // You should have received a copy of the GNU General Public License along
 // with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
 
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, fs, ops::Range, path::Path};
 
-use serde::Deserialize;
+use anyhow::{Context, Result, anyhow};
+use serde::{
+    Deserialize, Deserializer,
+    de::{IgnoredAny, MapAccess, SeqAccess, Visitor},
+};
+use toml::Spanned;
+
+use super::{
+    log::LogConfig,
+    matcher::{AppMatch, AppMatcher, AppPattern},
+    mode::{Mode, Profile},
+    topapp::{Aggregate, TopAppConfig},
+};
 
 #[derive(Clone, Deserialize)]
 pub struct ConfigData {
-    pub app: HashMap<String, String>,
-    pub on: String,
-    pub off: String,
+    pub app: HashMap<AppPattern, Mode>,
+    pub on: Mode,
+    pub off: Mode,
+    #[serde(default)]
+    pub mode: HashMap<Mode, Profile>,
+    #[serde(default)]
+    pub topapp: TopAppConfig,
+    #[serde(default)]
+    pub log: LogConfig,
+    #[serde(skip)]
+    matcher: AppMatcher,
+    #[serde(skip)]
+    source: String,
+}
+
+/// 仅在校验失败时用于定位出错的模式引用
+#[derive(Deserialize)]
+struct References {
+    app: HashMap<String, Spanned<String>>,
+    on: Spanned<String>,
+    off: Spanned<String>,
+    #[serde(default)]
+    mode: HashMap<Spanned<String>, toml::Value>,
+}
+
+impl ConfigData {
+    pub fn load(path: &Path) -> Result<Self> {
+        let context = fs::read_to_string(path)
+            .with_context(|| format!("无法读取配置文件{}", path.display()))?;
+        Self::parse(path, &context)
+    }
+
+    /// 测试用，`on = "balance"`、`off = "powersave"`，`app` 为 `[app]` 表的内容，其后可以再接其他表
+    #[cfg(test)]
+    pub fn for_test(app: &str) -> Self {
+        let context = format!("on = \"balance\"\noff = \"powersave\"\n\n[app]\n{app}");
+        Self::parse(Path::new("test.toml"), &context).unwrap()
+    }
+
+    /// `path` 仅用于错误信息
+    pub fn parse(path: &Path, context: &str) -> Result<Self> {
+        let mut data: Self =
+            toml::from_str(context).map_err(|e| describe(path, context, e.span(), e.message()))?;
+        for mode in Mode::BUILTIN {
+            let profile = Profile::builtin(&mode).unwrap();
+            data.mode.entry(mode).or_insert(profile);
+        }
+        data.validate(path, context)?;
+        data.matcher = AppMatcher::new(&data.app);
+        context.clone_into(&mut data.source);
+        Ok(data)
+    }
+
+    pub fn find_app(&self, package: &str) -> Option<AppMatch<'_>> {
+        self.matcher.find(package)
+    }
+
+    pub fn app_matches<'a, 'p>(
+        &'a self,
+        package: &'p str,
+    ) -> impl Iterator<Item = AppMatch<'a>> + use<'a, 'p> {
+        self.matcher.matches(package)
+    }
+
+    /// 生成此配置的原始文本
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn profile(&self, mode: &Mode) -> &Profile {
+        &self.mode[mode]
+    }
+
+    /// 未设置 `rank` 时，内置模式从 powersave 到 fast 依次为 0~3；
+    /// 自定义模式只有在 `aggregate = "top-app"` 时可以不设置，此时为 0
+    pub fn rank(&self, mode: &Mode) -> u32 {
+        self.profile(mode).rank.unwrap_or(match mode {
+            Mode::Powersave | Mode::Custom(_) => 0,
+            Mode::Balance => 1,
+            Mode::Performance => 2,
+            Mode::Fast => 3,
+        })
+    }
+
+    fn validate(&self, path: &Path, context: &str) -> Result<()> {
+        let references = || toml::from_str::<References>(context).ok();
+        let undefined = [&self.on, &self.off]
+            .into_iter()
+            .chain(self.app.values())
+            .find(|mode| !self.mode.contains_key(mode));
+        if let Some(undefined) = undefined {
+            let message = format!("未定义的模式 `{undefined}`");
+            let span = references().and_then(|refs| {
+                [refs.on, refs.off]
+                    .into_iter()
+                    .chain(refs.app.into_values())
+                    .find(|name| Mode::from(name.get_ref().clone()) == *undefined)
+                    .map(|name| name.span())
+            });
+            return Err(describe(path, context, span, &message));
+        }
+
+        // 没有 rank 的自定义模式无法与内置模式比较，会被当作最低而悄悄落选
+        if self.topapp.aggregate == Aggregate::TopApp {
+            return Ok(());
+        }
+        let unranked = self
+            .mode
+            .iter()
+            .filter(|(mode, profile)| matches!(mode, Mode::Custom(_)) && profile.rank.is_none())
+            .map(|(mode, _)| mode.to_string())
+            .min();
+        let Some(unranked) = unranked else {
+            return Ok(());
+        };
+        let message = format!(
+            "aggregate = \"{}\" 时自定义模式 `{unranked}` 需要设置 rank",
+            self.topapp.aggregate
+        );
+        let span = references().and_then(|refs| {
+            refs.mode
+                .into_keys()
+                .find(|name| *name.get_ref() == unranked)
+                .map(|name| name.span())
+        });
+        Err(describe(path, context, span, &message))
+    }
+}
+
+/// 把错误整理成 `文件:行:列: 键: 原因` 的形式
+fn describe(
+    path: &Path,
+    context: &str,
+    span: Option<Range<usize>>,
+    message: &str,
+) -> anyhow::Error {
+    let Some(span) = span else {
+        return anyhow!("无法解析配置文件{}: {message}", path.display());
+    };
+    let before = &context[..span.start];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+    let key = toml::from_str::<Node>(context)
+        .ok()
+        .and_then(|root| root.key_at(span.start))
+        .unwrap_or_else(|| String::from("<root>"));
+    anyhow!(
+        "无法解析配置文件{}:{line}:{column}: `{key}`: {}",
+        path.display(),
+        message.trim()
+    )
+}
+
+/// 配置文件中的键及其位置，仅用于找出错误位置所在的键；
+/// 未在原文中写出的表（如 `[mode.gaming]` 中的 `mode`）没有值的位置，因此只记录键的位置
+enum Node {
+    Table(Vec<(Spanned<String>, Self)>),
+    Array(Vec<Self>),
+    Value,
+}
+
+impl Node {
+    /// 原文中位于 `offset` 之前且离它最近的键，如 `app."com.example"`
+    fn key_at(&self, offset: usize) -> Option<String> {
+        let mut keys = Vec::new();
+        self.collect("", &mut keys);
+        keys.into_iter()
+            .filter(|(start, _)| *start <= offset)
+            .max_by_key(|(start, _)| *start)
+            .map(|(_, key)| key)
+    }
+
+    fn collect(&self, prefix: &str, keys: &mut Vec<(usize, String)>) {
+        match self {
+            Self::Table(entries) => {
+                for (key, value) in entries {
+                    let name = format!("{prefix}{}", quote(key.get_ref()));
+                    value.collect(&format!("{name}."), keys);
+                    keys.push((key.span().start, name));
+                }
+            }
+            Self::Array(items) => {
+                for item in items {
+                    item.collect(prefix, keys);
+                }
+            }
+            Self::Value => {}
+        }
+    }
+}
+
+/// 只含字母、数字、`-`、`_` 的键不加引号
+fn quote(key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        key.to_string()
+    } else {
+        format!("{key:?}")
+    }
+}
+
+impl<'de> Deserialize<'de> for Node {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(NodeVisitor)
+    }
+}
+
+struct NodeVisitor;
+
+impl<'de> Visitor<'de> for NodeVisitor {
+    type Value = Node;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("TOML 值")
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<Node, E> {
+        Ok(Node::Value)
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<Node, E> {
+        Ok(Node::Value)
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<Node, E> {
+        Ok(Node::Value)
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<Node, E> {
+        Ok(Node::Value)
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<Node, E> {
+        Ok(Node::Value)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Node, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Node::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Node, A::Error> {
+        let mut entries = Vec::new();
+        while let Some(key) = map.next_key::<Spanned<String>>()? {
+            // 日期时间以特殊的表传入，不再深入
+            if key.get_ref().starts_with("$__toml_private") {
+                map.next_value::<IgnoredAny>()?;
+                return Ok(Node::Value);
+            }
+            entries.push((key, map.next_value()?));
+        }
+        Ok(Node::Table(entries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = r#"on = "balance"
+off = "powersave"
+
+[app]
+"com.tencent.tmgr" = "fast"
+"bin.mt.plus" = "powersave"
+"#;
+
+    fn error(source: &str) -> String {
+        ConfigData::parse(Path::new("config.toml"), source)
+            .err()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn base_config_is_valid() {
+        assert!(ConfigData::parse(Path::new("config.toml"), BASE).is_ok());
+    }
+
+    #[test]
+    fn undefined_mode_points_at_its_key() {
+        let on = BASE.replace(r#"on = "balance""#, r#"on = "performence""#);
+        assert_eq!(
+            error(&on),
+            "无法解析配置文件config.toml:1:6: `on`: 未定义的模式 `performence`"
+        );
+
+        let off = BASE.replace(r#"off = "powersave""#, r#"off = "powersav""#);
+        assert_eq!(
+            error(&off),
+            "无法解析配置文件config.toml:2:7: `off`: 未定义的模式 `powersav`"
+        );
+
+        let app = BASE.replace(r#""fast""#, r#""fst""#);
+        assert_eq!(
+            error(&app),
+            r#"无法解析配置文件config.toml:5:22: `app."com.tencent.tmgr"`: 未定义的模式 `fst`"#
+        );
+    }
+
+    #[test]
+    fn misspelled_alias_in_inline_table() {
+        let source = r#"on = "balance"
+off = "powersave"
+app = { "bin.mt.plus" = "powersave", "com.tencent.tmgr" = "balnace" }
+"#;
+        assert_eq!(
+            error(source),
+            r#"无法解析配置文件config.toml:3:59: `app."com.tencent.tmgr"`: 未定义的模式 `balnace`"#
+        );
+    }
+
+    #[test]
+    fn type_error_in_multiline_array() {
+        let source = format!(
+            r#"{BASE}
+[topapp]
+ignore = [
+    "com.miui.screenrecorder",
+    42,
+]
+"#
+        );
+        let error = error(&source);
+        assert!(
+            error.starts_with("无法解析配置文件config.toml:11:5: `topapp.ignore`: "),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn error_inside_dotted_table_header() {
+        let source = format!(
+            r"{BASE}
+[mode.gaming]
+uclamp.background = {{ min = 0, max = 100 }}
+uclamp.foreground = {{ min = 0, max = 100 }}
+uclamp.top-app = {{ min = 0, max = 101 }}
+"
+        );
+        assert_eq!(
+            error(&source),
+            "无法解析配置文件config.toml:11:18: `mode.gaming.uclamp.top-app`: uclamp 取值需在0~100之间，实际为101"
+        );
+    }
+
+    #[test]
+    fn custom_modes_need_rank_when_aggregating() {
+        let source = format!(
+            r#"{BASE}
+[topapp]
+aggregate = "max"
+
+[mode.gaming]
+uclamp.background = {{ min = 0, max = 100 }}
+uclamp.foreground = {{ min = 0, max = 100 }}
+uclamp.top-app = {{ min = 0, max = 100 }}
+"#
+        );
+        assert_eq!(
+            error(&source),
+            "无法解析配置文件config.toml:11:7: `mode.gaming`: aggregate = \"max\" 时自定义模式 `gaming` 需要设置 rank"
+        );
+
+        let ranked = source.replace("[mode.gaming]", "[mode.gaming]\nrank = 4");
+        let config = ConfigData::parse(Path::new("config.toml"), &ranked).unwrap();
+        assert_eq!(config.rank(&Mode::Custom("gaming".into())), 4);
+
+        let top_app = source.replace(r#"aggregate = "max""#, r#"aggregate = "top-app""#);
+        let config = ConfigData::parse(Path::new("config.toml"), &top_app).unwrap();
+        assert_eq!(config.rank(&Mode::Custom("gaming".into())), 0);
+    }
 }
@@ -0,0 +1,165 @@
+// Copyright 2023-2025, [rust@localhost] $ (@3532340532)
+//
+// This file is part of EfficientScheduler.
+//
+// EfficientScheduler is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// EfficientScheduler is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along
+// with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono_tz::Tz;
+use log::LevelFilter;
+use serde::Deserialize;
+
+use super::path::module_dir;
+
+/// `[log]` 表
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct LogConfig {
+    /// 默认日志级别，可热重载
+    pub level: Level,
+    /// 按模块覆盖日志级别，键为模块路径中任意连续的几段，如 `looper::cpu`，可热重载
+    pub filters: BTreeMap<String, Level>,
+    /// 是否写入日志文件，关闭时只输出到标准输出
+    pub file: bool,
+    pub dir: PathBuf,
+    /// 单个日志文件的大小上限，单位 KiB
+    pub max_size: u64,
+    /// 除大小外，按时间轮转
+    pub rotate: Option<Rotate>,
+    /// 保留的旧日志文件数量
+    pub keep: usize,
+    /// 写入文件的同时输出到标准输出
+    pub stdout: bool,
+    /// 时间戳的时区，如 `Asia/Shanghai`，未设置时依次读取 `TZ` 与 `persist.sys.timezone`
+    pub timezone: Option<Timezone>,
+    pub timestamp: Timestamp,
+    pub format: Format,
+}
+
+/// off/error/warn/info/debug/trace，不区分大小写
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Level(pub LevelFilter);
+
+impl TryFrom<String> for Level {
+    type Error = String;
+
+    fn try_from(level: String) -> Result<Self, Self::Error> {
+        level
+            .parse()
+            .map(Self)
+            .map_err(|_| format!("无效的日志级别 `{level}`"))
+    }
+}
+
+/// tz 数据库中的时区名
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Timezone(pub Tz);
+
+impl TryFrom<String> for Timezone {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        name.parse()
+            .map(Self)
+            .map_err(|_| format!("未知的时区 `{name}`"))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Timestamp {
+    /// 本地时间
+    #[default]
+    Local,
+    /// UTC 时间加开机以来的秒数，便于与 `logcat -v monotonic` 对照
+    Monotonic,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Format {
+    /// 便于阅读的文本
+    #[default]
+    Text,
+    /// 每行一个 JSON 对象，包含级别、模块、事件类型与结构化字段
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rotate {
+    Day,
+    Hour,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: Level(LevelFilter::Info),
+            filters: BTreeMap::new(),
+            file: true,
+            dir: module_dir().join("log"),
+            max_size: 1024,
+            rotate: None,
+            keep: 3,
+            stdout: false,
+            timezone: None,
+            timestamp: Timestamp::Local,
+            format: Format::Text,
+        }
+    }
+}
+
+impl LogConfig {
+    /// 日志输出位置与格式是否相同，不同时需要重启才能生效
+    pub fn same_output(&self, other: &Self) -> bool {
+        let output = |config: &Self| {
+            (
+                config.file,
+                config.dir.clone(),
+                config.max_size,
+                config.rotate,
+                config.keep,
+                config.stdout,
+                config.timezone,
+                config.timestamp,
+                config.format,
+            )
+        };
+        output(self) == output(other)
+    }
+
+    /// 在完整加载配置之前读取 `[log]`，以便尽早初始化日志，
+    /// 出错时使用默认值，错误留给 [`ConfigData::load`](super::data::ConfigData::load) 报告
+    pub fn peek(path: &Path) -> Self {
+        #[derive(Deserialize)]
+        struct Partial {
+            #[serde(default)]
+            log: LogConfig,
+        }
+
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|context| toml::from_str::<Partial>(&context).ok())
+            .map(|partial| partial.log)
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,157 @@
+// Copyright 2023-2025, [rust@localhost] $ (@3532340532)
+//
+// This file is part of EfficientScheduler.
+//
+// EfficientScheduler is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// EfficientScheduler is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along
+// with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+
+use regex::Regex;
+use serde::Deserialize;
+
+use super::mode::Mode;
+
+/// `app` 表的键：以 `^` 开头为正则，含 `*`/`?` 为通配符，否则为完整包名
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub struct AppPattern(String);
+
+impl TryFrom<String> for AppPattern {
+    type Error = String;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        if pattern.starts_with('^') {
+            Regex::new(&pattern).map_err(|e| format!("无效的正则 `{pattern}`: {e}"))?;
+        }
+        Ok(Self(pattern))
+    }
+}
+
+impl AppPattern {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn kind(&self) -> MatchKind {
+        if self.0.starts_with('^') {
+            MatchKind::Regex
+        } else if self.0.contains(['*', '?']) {
+            MatchKind::Glob
+        } else {
+            MatchKind::Exact
+        }
+    }
+}
+
+fn glob_to_regex(glob: &str) -> Regex {
+    let mut pattern = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            c => pattern.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern).unwrap()
+}
+
+/// 按优先级从高到低排列
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MatchKind {
+    Exact,
+    Glob,
+    Regex,
+}
+
+#[derive(Debug)]
+pub struct AppMatch<'a> {
+    pub kind: MatchKind,
+    pub pattern: &'a str,
+    pub mode: &'a Mode,
+}
+
+/// 匹配优先级：完整包名 > 通配符 > 正则，同类中模式越长越优先，等长时按字典序
+#[derive(Clone, Default)]
+pub struct AppMatcher {
+    exact: HashMap<String, Mode>,
+    globs: Vec<(String, Regex, Mode)>,
+    regexes: Vec<(String, Regex, Mode)>,
+}
+
+impl AppMatcher {
+    pub fn new(app: &HashMap<AppPattern, Mode>) -> Self {
+        let mut matcher = Self::default();
+        for (pattern, mode) in app {
+            let text = pattern.as_str().to_string();
+            match pattern.kind() {
+                MatchKind::Exact => {
+                    matcher.exact.insert(text, mode.clone());
+                }
+                MatchKind::Glob => {
+                    let regex = glob_to_regex(&text);
+                    matcher.globs.push((text, regex, mode.clone()));
+                }
+                MatchKind::Regex => {
+                    let regex = Regex::new(&text).unwrap();
+                    matcher.regexes.push((text, regex, mode.clone()));
+                }
+            }
+        }
+        let longest_first = |(a, ..): &(String, Regex, Mode), (b, ..): &(String, Regex, Mode)| {
+            b.len().cmp(&a.len()).then_with(|| a.cmp(b))
+        };
+        matcher.globs.sort_by(longest_first);
+        matcher.regexes.sort_by(longest_first);
+        matcher
+    }
+
+    pub fn find(&self, package: &str) -> Option<AppMatch<'_>> {
+        self.matches(package).next()
+    }
+
+    /// 按优先级列出所有命中的规则，第一条即 [`find`](Self::find) 的结果
+    pub fn matches<'a, 'p>(
+        &'a self,
+        package: &'p str,
+    ) -> impl Iterator<Item = AppMatch<'a>> + use<'a, 'p> {
+        let exact = self
+            .exact
+            .get_key_value(package)
+            .map(|(pattern, mode)| AppMatch {
+                kind: MatchKind::Exact,
+                pattern,
+                mode,
+            });
+        let globs = self
+            .globs
+            .iter()
+            .filter(move |(_, regex, _)| regex.is_match(package))
+            .map(|(pattern, _, mode)| AppMatch {
+                kind: MatchKind::Glob,
+                pattern,
+                mode,
+            });
+        let regexes = self
+            .regexes
+            .iter()
+            .filter(move |(_, regex, _)| regex.is_match(package))
+            .map(|(pattern, _, mode)| AppMatch {
+                kind: MatchKind::Regex,
+                pattern,
+                mode,
+            });
+        exact.into_iter().chain(globs).chain(regexes)
+    }
+}
@@ -16,3 +16,8 @@
 // with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
 
 pub mod data;
+pub mod log;
+pub mod matcher;
+pub mod mode;
+pub mod path;
+pub mod topapp;
@@ -0,0 +1,203 @@
+// Copyright 2023-2025, [rust@localhost] $ (@3532340532)
+//
+// This file is part of EfficientScheduler.
+//
+// EfficientScheduler is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// EfficientScheduler is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along
+// with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{collections::HashMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum Mode {
+    Powersave,
+    Balance,
+    Performance,
+    Fast,
+    Custom(String),
+}
+
+impl From<String> for Mode {
+    fn from(name: String) -> Self {
+        match name.as_str() {
+            "powersave" | "standby" => Self::Powersave,
+            "balance" | "balanced" => Self::Balance,
+            "performance" | "perf" => Self::Performance,
+            "fast" | "pedestal" => Self::Fast,
+            _ => Self::Custom(name),
+        }
+    }
+}
+
+impl From<Mode> for String {
+    fn from(mode: Mode) -> Self {
+        mode.to_string()
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Powersave => "powersave",
+            Self::Balance => "balance",
+            Self::Performance => "performance",
+            Self::Fast => "fast",
+            Self::Custom(name) => name,
+        };
+        f.write_str(name)
+    }
+}
+
+impl Mode {
+    pub const BUILTIN: [Self; 4] = [
+        Self::Powersave,
+        Self::Balance,
+        Self::Performance,
+        Self::Fast,
+    ];
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub uclamp: Cpuctl,
+    #[serde(default)]
+    pub freq: HashMap<PolicyKey, FreqRange>,
+    /// 性能高低，用于多个应用同时可见时比较模式，见 [`ConfigData::rank`](super::data::ConfigData::rank)
+    pub rank: Option<u32>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Cpuctl {
+    pub background: Uclamp,
+    #[serde(rename = "top-app")]
+    pub top_app: Uclamp,
+    pub foreground: Uclamp,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "RawUclamp")]
+pub struct Uclamp {
+    pub max: usize,
+    pub min: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawUclamp {
+    max: usize,
+    min: usize,
+}
+
+impl TryFrom<RawUclamp> for Uclamp {
+    type Error = String;
+
+    fn try_from(raw: RawUclamp) -> Result<Self, Self::Error> {
+        if raw.max > 100 {
+            return Err(format!("uclamp 取值需在0~100之间，实际为{}", raw.max));
+        }
+        if raw.min > raw.max {
+            return Err(format!("uclamp min({}) 大于 max({})", raw.min, raw.max));
+        }
+        Ok(Self {
+            max: raw.max,
+            min: raw.min,
+        })
+    }
+}
+
+/// `default` 作用于所有未单独配置的 policy
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub enum PolicyKey {
+    Default,
+    Policy(usize),
+}
+
+impl TryFrom<String> for PolicyKey {
+    type Error = String;
+
+    fn try_from(key: String) -> Result<Self, Self::Error> {
+        if key == "default" {
+            return Ok(Self::Default);
+        }
+        key.strip_prefix("policy")
+            .and_then(|id| id.parse().ok())
+            .map(Self::Policy)
+            .ok_or_else(|| format!("无效的 policy `{key}`，需为 default 或 policy<N>"))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FreqRange {
+    pub max: FreqLimit,
+    pub min: FreqLimit,
+}
+
+/// 频率可写为具体的 kHz 值，或写为 `{ index = N }` 表示可用频率表中的位置：
+/// 频率表按从高到低排列，0 为最高频率，负数从最低频率倒数
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum FreqLimit {
+    Index { index: isize },
+    Khz(isize),
+}
+
+impl FreqLimit {
+    /// `freqs` 需按从高到低排列
+    pub fn resolve(self, freqs: &[isize]) -> Option<isize> {
+        match self {
+            Self::Khz(khz) => Some(khz),
+            Self::Index { index } => {
+                let len = freqs.len() as isize;
+                if len == 0 {
+                    return None;
+                }
+                let index = if index < 0 { len + index } else { index };
+                Some(freqs[index.clamp(0, len - 1) as usize])
+            }
+        }
+    }
+}
+
+impl Profile {
+    pub fn builtin(mode: &Mode) -> Option<Self> {
+        let (bg, ta, fg, max, min) = match mode {
+            Mode::Powersave => ((10, 5), (20, 10), (25, 20), -5, -3),
+            Mode::Balance => ((15, 10), (25, 20), (30, 20), 5, -6),
+            Mode::Performance => ((40, 10), (40, 20), (60, 20), 1, -6),
+            Mode::Fast => ((100, 10), (100, 20), (100, 20), 1, 1),
+            Mode::Custom(_) => return None,
+        };
+        let uclamp = |(max, min)| Uclamp { max, min };
+        Some(Self {
+            uclamp: Cpuctl {
+                background: uclamp(bg),
+                top_app: uclamp(ta),
+                foreground: uclamp(fg),
+            },
+            freq: HashMap::from([(
+                PolicyKey::Default,
+                FreqRange {
+                    max: FreqLimit::Index { index: max },
+                    min: FreqLimit::Index { index: min },
+                },
+            )]),
+            rank: None,
+        })
+    }
+}
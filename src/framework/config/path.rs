@@ -0,0 +1,163 @@
+// Copyright 2023-2025, [rust@localhost] $ (@3532340532)
+//
+// This file is part of EfficientScheduler.
+//
+// EfficientScheduler is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// EfficientScheduler is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along
+// with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, bail};
+
+use crate::framework::kernel;
+
+const TEMPLATE: &str = include_str!("../../../modules/config.toml");
+const CONFIG_NAME: &str = "config.toml";
+const MODULE_DIR: &str = "/data/adb/modules/EfficientScheduler";
+const SDCARD_DIR: &str = "/sdcard/Android/EfficientScheduler";
+
+/// 与内核节点一样映射到 `--root` 下
+pub fn module_dir() -> PathBuf {
+    kernel::path(MODULE_DIR)
+}
+
+/// 按 命令行/环境变量 -> 模块目录 -> `/data/adb/modules` -> `/sdcard/Android` 的顺序查找配置文件，
+/// 均不存在时按同样顺序写出默认配置
+pub fn resolve(explicit: Option<PathBuf>) -> Result<PathBuf> {
+    resolve_in(explicit, &candidates())
+}
+
+fn resolve_in(explicit: Option<PathBuf>, candidates: &[PathBuf]) -> Result<PathBuf> {
+    if let Some(path) = explicit {
+        if !path.exists() {
+            generate(&path)?;
+        }
+        return Ok(path);
+    }
+
+    if let Some(path) = find_in(None, candidates) {
+        return Ok(path);
+    }
+    for path in candidates {
+        match generate(path) {
+            Ok(()) => return Ok(path.clone()),
+            Err(e) => log::warn!("无法生成配置文件{}: {e}", path.display()),
+        }
+    }
+    bail!("无法找到或生成配置文件")
+}
+
+/// 与 [`resolve`] 的查找顺序相同，但不会生成默认配置
+pub fn find(explicit: Option<&Path>) -> Option<PathBuf> {
+    find_in(explicit, &candidates())
+}
+
+fn find_in(explicit: Option<&Path>, candidates: &[PathBuf]) -> Option<PathBuf> {
+    explicit.map_or_else(
+        || candidates.iter().find(|path| path.is_file()).cloned(),
+        |path| path.is_file().then(|| path.to_path_buf()),
+    )
+}
+
+fn candidates() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Some(dir) = env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+    {
+        paths.push(dir.join(CONFIG_NAME));
+    }
+    for dir in [MODULE_DIR, SDCARD_DIR] {
+        let path = kernel::path(dir).join(CONFIG_NAME);
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+fn generate(path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, TEMPLATE)?;
+    log::info!("未找到配置文件，已生成默认配置{}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explicit_path_wins_and_is_generated_when_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let fallback = dir.path().join("fallback.toml");
+        fs::write(&fallback, "").unwrap();
+        let candidates = [fallback];
+
+        let explicit = dir.path().join("explicit.toml");
+        fs::write(&explicit, "# 已有配置").unwrap();
+        assert_eq!(
+            find_in(Some(&explicit), &candidates),
+            Some(explicit.clone())
+        );
+        assert_eq!(
+            resolve_in(Some(explicit.clone()), &candidates).unwrap(),
+            explicit
+        );
+        assert_eq!(fs::read_to_string(&explicit).unwrap(), "# 已有配置");
+
+        let missing = dir.path().join("new/config.toml");
+        assert_eq!(find_in(Some(&missing), &candidates), None);
+        assert_eq!(
+            resolve_in(Some(missing.clone()), &candidates).unwrap(),
+            missing
+        );
+        assert_eq!(fs::read_to_string(&missing).unwrap(), TEMPLATE);
+    }
+
+    #[test]
+    fn fallbacks_are_searched_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let candidates =
+            ["module", "adb", "sdcard"].map(|name| dir.path().join(name).join(CONFIG_NAME));
+        fs::create_dir_all(candidates[1].parent().unwrap()).unwrap();
+        fs::create_dir_all(candidates[2].parent().unwrap()).unwrap();
+        fs::write(&candidates[1], "").unwrap();
+        fs::write(&candidates[2], "").unwrap();
+
+        assert_eq!(find_in(None, &candidates), Some(candidates[1].clone()));
+        assert_eq!(resolve_in(None, &candidates).unwrap(), candidates[1]);
+        assert!(!candidates[0].exists());
+    }
+
+    #[test]
+    fn template_goes_to_first_writable_fallback() {
+        let dir = tempfile::tempdir().unwrap();
+        // 父目录是普通文件，无法在其中生成配置
+        let blocked = dir.path().join("blocked");
+        fs::write(&blocked, "").unwrap();
+        let candidates = [
+            blocked.join(CONFIG_NAME),
+            dir.path().join("sdcard").join(CONFIG_NAME),
+        ];
+
+        assert_eq!(find_in(None, &candidates), None);
+        assert_eq!(resolve_in(None, &candidates).unwrap(), candidates[1]);
+        assert_eq!(fs::read_to_string(&candidates[1]).unwrap(), TEMPLATE);
+    }
+}
@@ -0,0 +1,98 @@
+// Copyright 2023-2025, [rust@localhost] $ (@3532340532)
+//
+// This file is part of EfficientScheduler.
+//
+// EfficientScheduler is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// EfficientScheduler is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along
+// with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
+
+use std::fmt;
+
+use serde::Deserialize;
+
+/// `[topapp]` 表，决定多个应用窗口同时可见时以哪个作为顶层应用
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct TopAppConfig {
+    pub policy: TopAppPolicy,
+    /// 不会作为顶层应用的包名，如聊天气泡、录屏等悬浮窗
+    pub ignore: Vec<String>,
+    pub aggregate: Aggregate,
+    pub weight: Weight,
+}
+
+impl TopAppConfig {
+    pub fn ignores(&self, package: &str) -> bool {
+        self.ignore.iter().any(|ignored| ignored == package)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TopAppPolicy {
+    /// 焦点窗口所属的应用
+    #[default]
+    Focused,
+    /// 最上层的悬浮窗口（悬浮窗、对话框、小窗、画中画）所属的应用，没有时同 `focused`
+    Floating,
+    /// 可见窗口中命中 `[app]` 规则优先级最高的应用，优先级相同时取焦点窗口，都未命中时同 `focused`
+    Priority,
+}
+
+/// 多个应用同时可见时如何决定模式，被忽略的包名不参与
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Aggregate {
+    /// 只看 `policy` 选出的顶层应用
+    #[default]
+    TopApp,
+    /// 各可见应用对应的模式中 `rank` 最高的，相同时取焦点窗口
+    Max,
+    /// 各可见应用按窗口的权重为对应的模式投票，票数相同时取 `rank` 高的
+    Weighted,
+}
+
+impl fmt::Display for Aggregate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TopApp => write!(f, "top-app"),
+            Self::Max => write!(f, "max"),
+            Self::Weighted => write!(f, "weighted"),
+        }
+    }
+}
+
+/// `aggregate = "weighted"` 时各类窗口的票数
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Weight {
+    pub fullscreen: u32,
+    pub multi_window: u32,
+    pub freeform: u32,
+    pub pinned: u32,
+    pub overlay: u32,
+    /// 焦点窗口额外增加的票数
+    pub focused: u32,
+}
+
+impl Default for Weight {
+    fn default() -> Self {
+        Self {
+            fullscreen: 4,
+            multi_window: 2,
+            freeform: 1,
+            pinned: 1,
+            overlay: 0,
+            focused: 2,
+        }
+    }
+}
@@ -0,0 +1,60 @@
+// Copyright 2023-2025, [rust@localhost] $ (@3532340532)
+//
+// This file is part of EfficientScheduler.
+//
+// EfficientScheduler is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// EfficientScheduler is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along
+// with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    path::Path,
+    time::Duration,
+};
+
+use anyhow::{Context, Result, bail};
+
+use super::{Request, Response};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct Client {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+impl Client {
+    pub fn connect(path: &Path) -> Result<Self> {
+        let stream = UnixStream::connect(path)
+            .with_context(|| format!("无法连接守护进程{}，是否正在运行", path.display()))?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        Ok(Self {
+            writer: stream.try_clone()?,
+            reader: BufReader::new(stream),
+        })
+    }
+
+    pub fn request(&mut self, request: &Request) -> Result<Response> {
+        self.send_line(&serde_json::to_string(request)?)
+    }
+
+    /// 发送任意一行文本，用于调试协议
+    pub fn send_line(&mut self, line: &str) -> Result<Response> {
+        writeln!(self.writer, "{line}")?;
+        let mut response = String::new();
+        if self.reader.read_line(&mut response)? == 0 {
+            bail!("守护进程关闭了连接");
+        }
+        serde_json::from_str(&response).context("无法解析守护进程的响应")
+    }
+}
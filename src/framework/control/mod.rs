@@ -0,0 +1,192 @@
+// Copyright 2023-2025, [rust@localhost] $ (@3532340532)
+//
+// This file is part of EfficientScheduler.
+//
+// EfficientScheduler is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// EfficientScheduler is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along
+// with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
+
+//! 守护进程的本地控制接口
+//!
+//! 基于 Unix 域套接字，每行一个 JSON 请求，对应一行 JSON 响应：
+//!
+//! ```text
+//! -> {"cmd":"set-mode","mode":"fast"}
+//! <- {"ok":true,"data":{...}}
+//! ```
+
+pub mod client;
+pub mod server;
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{config::path::module_dir, scheduler::manual::Until};
+
+const SOCKET_NAME: &str = "control.sock";
+
+pub fn default_socket() -> PathBuf {
+    module_dir().join(SOCKET_NAME)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "kebab-case")]
+pub enum Request {
+    /// 当前模式、原因与观测到的系统状态
+    Status,
+    /// 手动覆盖模式，优先级高于 Scene 与所有规则，
+    /// `duration` 秒后或满足 `until` 条件时自动清除
+    SetMode {
+        mode: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        duration: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        until: Option<Until>,
+    },
+    ClearOverride,
+    /// 立即重新读取配置文件，失败时继续使用旧配置
+    ReloadConfig,
+    /// 正在使用的配置文件路径与内容
+    GetConfig,
+    /// 按规则的求值顺序说明当前模式的由来
+    Explain,
+    /// 还原所有被修改过的内核节点，应答后守护进程退出，部分节点未能还原时也一样
+    Restore,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub data: Value,
+}
+
+impl Response {
+    pub const fn ok(data: Value) -> Self {
+        Self {
+            ok: true,
+            error: None,
+            data,
+        }
+    }
+
+    pub const fn error(message: String) -> Self {
+        Self {
+            ok: false,
+            error: Some(message),
+            data: Value::Null,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc, thread};
+
+    use serde_json::json;
+
+    use super::{client::Client, server, *};
+    use crate::framework::scheduler::looper::Event;
+
+    /// 代替 Looper 应答请求
+    fn spawn_responder() -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SOCKET_NAME);
+        let (sender, receiver) = mpsc::channel();
+        server::serve(&path, sender).unwrap();
+        thread::spawn(move || {
+            for event in receiver {
+                if let Event::Control(request, reply) = event {
+                    let response = match request {
+                        Request::SetMode { mode, .. } if mode == "undefined" => {
+                            Response::error(format!("未定义的模式 `{mode}`"))
+                        }
+                        request => Response::ok(json!({ "echo": request })),
+                    };
+                    let _ = reply.send(response);
+                }
+            }
+        });
+        (dir, path)
+    }
+
+    #[test]
+    fn request_wire_format() {
+        let request = Request::SetMode {
+            mode: "fast".into(),
+            duration: None,
+            until: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&request).unwrap(),
+            r#"{"cmd":"set-mode","mode":"fast"}"#
+        );
+        let request: Request = serde_json::from_str(r#"{"cmd":"clear-override"}"#).unwrap();
+        assert_eq!(request, Request::ClearOverride);
+        let request: Request = serde_json::from_str(
+            r#"{"cmd":"set-mode","mode":"fast","duration":600,"until":"topapp-change"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            request,
+            Request::SetMode {
+                mode: "fast".into(),
+                duration: Some(600),
+                until: Some(Until::TopappChange),
+            }
+        );
+    }
+
+    #[test]
+    fn round_trip_over_socket() {
+        let (_dir, path) = spawn_responder();
+        let mut client = Client::connect(&path).unwrap();
+        for request in [
+            Request::Status,
+            Request::SetMode {
+                mode: "fast".into(),
+                duration: Some(600),
+                until: Some(Until::ScreenOff),
+            },
+            Request::ClearOverride,
+            Request::ReloadConfig,
+            Request::GetConfig,
+        ] {
+            let response = client.request(&request).unwrap();
+            assert!(response.ok);
+            assert_eq!(response.data, json!({ "echo": request }));
+        }
+
+        let response = client
+            .request(&Request::SetMode {
+                mode: "undefined".into(),
+                duration: None,
+                until: None,
+            })
+            .unwrap();
+        assert!(!response.ok);
+        assert_eq!(response.error.as_deref(), Some("未定义的模式 `undefined`"));
+    }
+
+    #[test]
+    fn malformed_request_keeps_connection() {
+        let (_dir, path) = spawn_responder();
+        let mut client = Client::connect(&path).unwrap();
+        let response = client.send_line("{\"cmd\":\"reboot\"}").unwrap();
+        assert!(!response.ok);
+        assert!(client.request(&Request::Status).unwrap().ok);
+    }
+}
@@ -0,0 +1,109 @@
+// Copyright 2023-2025, [rust@localhost] $ (@3532340532)
+//
+// This file is part of EfficientScheduler.
+//
+// EfficientScheduler is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// EfficientScheduler is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along
+// with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::Path,
+    process,
+    sync::mpsc::{self, Sender},
+    thread,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+
+use super::{Request, Response};
+use crate::framework::scheduler::looper::Event;
+
+/// Looper 每秒至少处理一次事件，超过此时间仍未应答视为卡死
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 在 `path` 上监听，请求转交给 Looper 处理，每个连接一个线程
+pub fn serve(path: &Path, events: Sender<Event>) -> Result<()> {
+    // 上次退出时遗留的套接字文件
+    let _ = fs::remove_file(path);
+    let listener =
+        UnixListener::bind(path).with_context(|| format!("无法监听{}", path.display()))?;
+    let path = path.to_path_buf();
+    thread::Builder::new()
+        .name("control".into())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let events = events.clone();
+                        let path = path.clone();
+                        thread::spawn(move || handle(stream, &events, &path));
+                    }
+                    Err(e) => log::error!("控制连接失败：{e}"),
+                }
+            }
+        })?;
+    Ok(())
+}
+
+fn handle(stream: UnixStream, events: &Sender<Event>, path: &Path) {
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            return;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (response, exit) = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => {
+                let restore = request == Request::Restore;
+                match dispatch(request, events) {
+                    // 即使部分节点未能还原，写入也已停止，不能再继续调度
+                    Ok(response) => (response, restore),
+                    Err(message) => (Response::error(String::from(message)), false),
+                }
+            }
+            Err(e) => (Response::error(format!("无法解析请求：{e}")), false),
+        };
+        let Ok(response) = serde_json::to_string(&response) else {
+            return;
+        };
+        let written = writeln!(writer, "{response}");
+        // 已尝试还原内核节点，之后的修改都不会生效
+        if exit {
+            if let Err(e) = fs::remove_file(path) {
+                log::warn!("无法删除套接字{}: {e}", path.display());
+            }
+            process::exit(0);
+        }
+        if written.is_err() {
+            return;
+        }
+    }
+}
+
+/// Looper 未处理请求时返回原因
+fn dispatch(request: Request, events: &Sender<Event>) -> Result<Response, &'static str> {
+    let (reply, receiver) = mpsc::channel();
+    events
+        .send(Event::Control(request, reply))
+        .map_err(|_| "调度器已停止")?;
+    receiver
+        .recv_timeout(REPLY_TIMEOUT)
+        .map_err(|_| "调度器未响应")
+}
@@ -0,0 +1,205 @@
+// Copyright 2023-2025, [rust@localhost] $ (@3532340532)
+//
+// This file is part of EfficientScheduler.
+//
+// EfficientScheduler is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// EfficientScheduler is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along
+// with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+    ffi::CString,
+    fs, io,
+    os::unix::{ffi::OsStrExt, fs::PermissionsExt},
+    path::{Path, PathBuf},
+    ptr,
+    sync::{
+        OnceLock,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use anyhow::Result;
+use libc::{MS_BIND, MS_REC, mount, umount, umount2};
+
+pub mod snapshot;
+
+static ROOT: OnceLock<PathBuf> = OnceLock::new();
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// 只能设置一次，需在访问任何内核节点之前调用
+pub fn set_root(root: PathBuf) {
+    let _ = ROOT.set(root);
+}
+
+/// 把内核节点的绝对路径映射到模拟根目录下，未设置根目录时原样返回
+pub fn path(path: impl AsRef<Path>) -> PathBuf {
+    let path = path.as_ref();
+    ROOT.get().map_or_else(
+        || path.to_path_buf(),
+        |root| root.join(path.strip_prefix("/").unwrap_or(path)),
+    )
+}
+
+/// 开启后所有写入、权限修改与挂载只记录日志而不执行
+pub fn set_dry_run(enabled: bool) {
+    if enabled {
+        log::info!("已开启 dry-run，不会修改任何内核节点");
+    }
+    DRY_RUN.store(enabled, Ordering::Release);
+}
+
+fn dry_run() -> bool {
+    DRY_RUN.load(Ordering::Acquire)
+}
+
+/// 修改前会记录节点的原始值，退出时还原
+pub fn write(path: &Path, value: &str) -> Result<()> {
+    snapshot::track(path, false, || Ok(raw_write(path, value)?))
+}
+
+/// 不记录到快照，用于写入本身无需还原的节点
+pub fn write_untracked(path: &Path, value: &str) -> Result<()> {
+    Ok(raw_write(path, value)?)
+}
+
+pub fn chmod(path: &Path, mode: u32) -> Result<()> {
+    snapshot::track(path, false, || Ok(raw_chmod(path, mode)?))
+}
+
+fn raw_write(path: &Path, value: &str) -> io::Result<()> {
+    if dry_run() {
+        let old = fs::read_to_string(path).unwrap_or_default();
+        log::info!(
+            event = "write",
+            path:% = path.display(),
+            value,
+            dry_run = true;
+            "[dry-run] 写入{}: {} -> {value}",
+            path.display(),
+            old.trim()
+        );
+        return Ok(());
+    }
+    fs::write(path, value)?;
+    log::trace!(
+        event = "write",
+        path:% = path.display(),
+        value;
+        "已写入{}: {value}",
+        path.display()
+    );
+    Ok(())
+}
+
+fn raw_chmod(path: &Path, mode: u32) -> io::Result<()> {
+    if dry_run() {
+        let old = fs::metadata(path)
+            .map(|m| format!("{:o}", m.permissions().mode() & 0o7777))
+            .unwrap_or_default();
+        log::info!(
+            event = "chmod",
+            path:% = path.display(),
+            value:% = format_args!("{mode:o}"),
+            dry_run = true;
+            "[dry-run] 修改权限{}: {old} -> {mode:o}",
+            path.display()
+        );
+        return Ok(());
+    }
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+/// 写入后设为只读并用同值的文件覆盖挂载，防止被其他进程改回
+pub fn lock_value(path: &Path, value: &str) -> Result<()> {
+    let mount_path = self::path(format!("/cache/mount_mask_{value}"));
+    snapshot::track(path, true, || {
+        // 未挂载过时卸载必然失败，忽略即可
+        let _ = unmount(path);
+        if let Err(e) = raw_chmod(path, 0o644) {
+            log::error!(
+                event = "chmod_failed",
+                path:% = path.display();
+                "无法设置权限{}: {e}",
+                path.display()
+            );
+        }
+        if let Err(e) = raw_write(path, value) {
+            log::error!(
+                event = "write_failed",
+                path:% = path.display(),
+                value;
+                "无法写入文件{}: {e}",
+                path.display()
+            );
+        }
+        if let Err(e) = raw_chmod(path, 0o444) {
+            log::error!(
+                event = "chmod_failed",
+                path:% = path.display();
+                "无法设置权限{}: {e}",
+                path.display()
+            );
+        }
+        if let Err(e) = raw_write(&mount_path, value) {
+            log::error!(
+                event = "write_failed",
+                path:% = mount_path.display(),
+                value;
+                "无法写入文件{}: {e}",
+                mount_path.display()
+            );
+        }
+        mount_bind(&mount_path, path)
+    })
+}
+
+fn mount_bind(src_path: &Path, dest_path: &Path) -> Result<()> {
+    if dry_run() {
+        log::info!(
+            "[dry-run] 挂载{} -> {}",
+            src_path.display(),
+            dest_path.display()
+        );
+        return Ok(());
+    }
+    let src_path = CString::new(src_path.as_os_str().as_bytes())?;
+    let dest_path = CString::new(dest_path.as_os_str().as_bytes())?;
+
+    unsafe {
+        umount2(dest_path.as_ptr(), libc::MNT_DETACH);
+
+        if mount(
+            src_path.as_ptr().cast(),
+            dest_path.as_ptr().cast(),
+            ptr::null(),
+            MS_BIND | MS_REC,
+            ptr::null(),
+        ) != 0
+        {
+            return Err(io::Error::last_os_error().into());
+        }
+    }
+
+    Ok(())
+}
+
+fn unmount(file_system: &Path) -> Result<()> {
+    if dry_run() {
+        log::info!("[dry-run] 卸载{}", file_system.display());
+        return Ok(());
+    }
+    let path = CString::new(file_system.as_os_str().as_bytes())?;
+    if unsafe { umount(path.as_ptr()) } != 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    Ok(())
+}
@@ -0,0 +1,289 @@
+// Copyright 2023-2025, [rust@localhost] $ (@3532340532)
+//
+// This file is part of EfficientScheduler.
+//
+// EfficientScheduler is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// EfficientScheduler is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along
+// with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    process,
+    sync::{LazyLock, Mutex, MutexGuard},
+    thread,
+};
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use signal_hook::{
+    consts::{SIGINT, SIGTERM},
+    iterator::Signals,
+};
+
+const SNAPSHOT_PATH: &str = "/dev/efficient_scheduler_snapshot.json";
+
+/// 首次修改前节点的原始状态
+#[derive(Serialize, Deserialize)]
+struct Original {
+    value: Option<String>,
+    mode: u32,
+    mounted: bool,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Snapshot {
+    entries: BTreeMap<PathBuf, Original>,
+    /// 快照文件的位置
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(skip)]
+    restored: bool,
+}
+
+/// 快照保存在 tmpfs 上，进程崩溃后重启仍能读到真正的原始值，重启设备后自然失效
+static SNAPSHOT: LazyLock<Mutex<Snapshot>> =
+    LazyLock::new(|| Mutex::new(Snapshot::load(super::path(SNAPSHOT_PATH))));
+
+fn lock() -> MutexGuard<'static, Snapshot> {
+    SNAPSHOT
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+impl Snapshot {
+    fn load(path: PathBuf) -> Self {
+        let snapshot: Option<Self> = fs::read_to_string(&path)
+            .ok()
+            .and_then(|context| serde_json::from_str(&context).ok());
+        Self {
+            path,
+            ..snapshot.unwrap_or_default()
+        }
+    }
+
+    fn record(&mut self, path: &Path, mounts: bool) {
+        if let Some(original) = self.entries.get_mut(path) {
+            if mounts && !original.mounted {
+                original.mounted = true;
+                self.save();
+            }
+            return;
+        }
+        let Ok(metadata) = fs::metadata(path) else {
+            return;
+        };
+        let value = fs::read_to_string(path)
+            .ok()
+            .map(|value| value.trim().to_string());
+        self.entries.insert(
+            path.to_path_buf(),
+            Original {
+                value,
+                mode: metadata.permissions().mode() & 0o7777,
+                mounted: mounts,
+            },
+        );
+        self.save();
+    }
+
+    fn save(&self) {
+        let result = serde_json::to_string_pretty(self)
+            .map_err(io::Error::other)
+            .and_then(|context| fs::write(&self.path, context));
+        if let Err(e) = result {
+            log::error!("无法保存快照{}: {e}", self.path.display());
+        }
+    }
+
+    fn track<T: Default>(
+        &mut self,
+        path: &Path,
+        mounts: bool,
+        modify: impl FnOnce() -> Result<T>,
+    ) -> Result<T> {
+        if self.restored {
+            return Ok(T::default());
+        }
+        if !super::dry_run() {
+            self.record(path, mounts);
+        }
+        modify()
+    }
+
+    fn restore(&mut self) -> Result<()> {
+        self.restored = true;
+        if self.entries.is_empty() {
+            log::info!("没有需要还原的内核节点");
+            return Ok(());
+        }
+        let mut failed = BTreeMap::new();
+        for (path, original) in &self.entries {
+            if original.mounted
+                && let Err(e) = super::unmount(path)
+            {
+                failed.insert(path.clone(), e);
+            }
+        }
+        // cpufreq 的 min/max 互相约束，第一轮失败的节点在其余节点还原后重试一次
+        let retry: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|(path, original)| restore_one(path, original).is_err())
+            .collect();
+        for (path, original) in retry {
+            if let Err(e) = restore_one(path, original) {
+                failed.entry(path.clone()).or_insert_with(|| e.into());
+            }
+        }
+        for (path, e) in &failed {
+            log::error!(
+                event = "restore_failed",
+                path:% = path.display();
+                "无法还原{}: {e}",
+                path.display()
+            );
+        }
+        log::info!("已还原{}个内核节点", self.entries.len() - failed.len());
+        self.entries.retain(|path, _| failed.contains_key(path));
+        if self.entries.is_empty() {
+            if !super::dry_run() {
+                let _ = fs::remove_file(&self.path);
+            }
+            return Ok(());
+        }
+        self.save();
+        let paths: Vec<_> = failed
+            .keys()
+            .map(|path| path.display().to_string())
+            .collect();
+        bail!("{}个内核节点未能还原: {}", paths.len(), paths.join(", "))
+    }
+}
+
+/// 在快照锁内执行对 `path` 的修改，首次修改前记录其原始值与权限；
+/// 还原之后不再执行任何修改
+pub(super) fn track<T: Default>(
+    path: &Path,
+    mounts: bool,
+    modify: impl FnOnce() -> Result<T>,
+) -> Result<T> {
+    lock().track(path, mounts, modify)
+}
+
+/// 是否已经还原过，之后的修改都会被忽略
+pub fn restored() -> bool {
+    lock().restored
+}
+
+/// 还原所有记录过的节点，未能还原的节点保留在快照中，下次启动后可再次还原；
+/// 无论结果如何都不再执行之后的修改，调用者应随后退出
+pub fn restore() -> Result<()> {
+    lock().restore()
+}
+
+fn restore_one(path: &Path, original: &Original) -> io::Result<()> {
+    super::raw_chmod(path, 0o644)?;
+    if let Some(value) = &original.value {
+        super::raw_write(path, value)?;
+    }
+    super::raw_chmod(path, original.mode)
+}
+
+/// 收到 SIGTERM/SIGINT 时还原所有内核节点后退出
+pub fn restore_on_exit() -> Result<()> {
+    let mut signals = Signals::new([SIGTERM, SIGINT])?;
+    thread::Builder::new()
+        .name("signals".into())
+        .spawn(move || {
+            if let Some(signal) = signals.forever().next() {
+                log::info!("收到信号{signal}，正在还原内核节点");
+                if let Err(e) = restore() {
+                    log::error!("{e}");
+                }
+                process::exit(0);
+            }
+        })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mode(path: &Path) -> u32 {
+        fs::metadata(path).unwrap().permissions().mode() & 0o7777
+    }
+
+    /// 与 `kernel::write`、`kernel::chmod` 相同，但使用独立的快照，不依赖全局的根目录
+    fn write(snapshot: &mut Snapshot, path: &Path, value: &str) {
+        snapshot
+            .track(path, false, || Ok(super::super::raw_write(path, value)?))
+            .unwrap();
+    }
+
+    fn chmod(snapshot: &mut Snapshot, path: &Path, mode: u32) {
+        snapshot
+            .track(path, false, || Ok(super::super::raw_chmod(path, mode)?))
+            .unwrap();
+    }
+
+    #[test]
+    fn restore_brings_back_values_and_modes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut snapshot = Snapshot::load(dir.path().join("snapshot.json"));
+        let max = dir.path().join("scaling_max_freq");
+        let min = dir.path().join("scaling_min_freq");
+        let gone = dir.path().join("scaling_governor");
+        for (path, value, mode) in [(&max, "2000000\n", 0o644), (&min, "300000\n", 0o444)] {
+            fs::write(path, value).unwrap();
+            fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+        }
+        fs::write(&gone, "schedutil\n").unwrap();
+
+        write(&mut snapshot, &max, "1000000");
+        chmod(&mut snapshot, &max, 0o444);
+        chmod(&mut snapshot, &min, 0o644);
+        write(&mut snapshot, &min, "500000");
+        chmod(&mut snapshot, &min, 0o440);
+        // 之后的修改不会覆盖记录的原始值
+        write(&mut snapshot, &max, "1200000");
+        write(&mut snapshot, &gone, "performance");
+        assert_eq!(fs::read_to_string(&max).unwrap(), "1200000");
+        assert_eq!(mode(&min), 0o440);
+        assert!(snapshot.path.exists());
+
+        // 重启后从文件读到的仍是最初的原始值
+        let saved = Snapshot::load(snapshot.path.clone());
+        assert_eq!(saved.entries[&max].value.as_deref(), Some("2000000"));
+
+        fs::remove_file(&gone).unwrap();
+        let error = snapshot.restore().unwrap_err().to_string();
+        assert!(error.contains("scaling_governor"), "{error}");
+        assert_eq!(fs::read_to_string(&max).unwrap(), "2000000");
+        assert_eq!(mode(&max), 0o644);
+        assert_eq!(fs::read_to_string(&min).unwrap(), "300000");
+        assert_eq!(mode(&min), 0o444);
+
+        // 未能还原的节点留在快照中
+        let saved = Snapshot::load(snapshot.path.clone());
+        assert!(saved.entries.contains_key(&gone));
+        assert!(!saved.entries.contains_key(&max));
+
+        // 即使部分节点未能还原，之后也不再修改
+        assert!(snapshot.restored);
+        write(&mut snapshot, &max, "1000000");
+        assert_eq!(fs::read_to_string(&max).unwrap(), "2000000");
+    }
+}
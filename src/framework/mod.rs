@@ -16,5 +16,8 @@
 // with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
 
 pub mod config;
+pub mod control;
+pub mod kernel;
 pub mod scheduler;
-pub use config::data::ConfigData;
+pub mod watcher;
+pub use config::{data::ConfigData, mode::Mode};
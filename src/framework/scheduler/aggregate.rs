@@ -0,0 +1,196 @@
+// Copyright 2023-2025, [rust@localhost] $ (@3532340532)
+//
+// This file is part of EfficientScheduler.
+//
+// EfficientScheduler is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// EfficientScheduler is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along
+// with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{cmp::Reverse, collections::HashMap};
+
+use crate::framework::{
+    ConfigData, Mode,
+    config::{
+        matcher::AppMatch,
+        topapp::{Aggregate, Weight},
+    },
+};
+
+use super::dump::window::{VisibleWindow, WindowType, WindowingMode};
+
+/// 一个可见应用，同一应用有多个窗口时合并为一个
+#[derive(Debug)]
+pub struct Candidate<'a> {
+    pub package: &'a str,
+    /// 命中的应用规则，未命中时使用 `on`
+    pub found: Option<AppMatch<'a>>,
+    pub mode: &'a Mode,
+    pub rank: u32,
+    /// 该应用所有窗口中最大的权重
+    pub weight: u32,
+    pub focused: bool,
+}
+
+impl Weight {
+    const fn of(&self, window: &VisibleWindow) -> u32 {
+        let weight = match (window.kind, window.mode) {
+            (WindowType::Overlay, _) => self.overlay,
+            (_, WindowingMode::Pinned) => self.pinned,
+            (_, WindowingMode::Freeform) => self.freeform,
+            (_, WindowingMode::MultiWindow) => self.multi_window,
+            _ => self.fullscreen,
+        };
+        if window.focused {
+            weight + self.focused
+        } else {
+            weight
+        }
+    }
+}
+
+/// 按 dumpsys 输出的顺序列出参与聚合的应用，忽略列表中的包名不包含在内
+pub fn candidates<'a>(config: &'a ConfigData, windows: &'a [VisibleWindow]) -> Vec<Candidate<'a>> {
+    let topapp = &config.topapp;
+    let mut candidates: Vec<Candidate> = Vec::new();
+    for window in windows {
+        if topapp.ignores(&window.package) {
+            continue;
+        }
+        let weight = topapp.weight.of(window);
+        if let Some(candidate) = candidates
+            .iter_mut()
+            .find(|candidate| candidate.package == window.package)
+        {
+            candidate.weight = candidate.weight.max(weight);
+            candidate.focused |= window.focused;
+            continue;
+        }
+        let found = config.find_app(&window.package);
+        let mode = found.as_ref().map_or(&config.on, |found| found.mode);
+        candidates.push(Candidate {
+            package: &window.package,
+            rank: config.rank(mode),
+            found,
+            mode,
+            weight,
+            focused: window.focused,
+        });
+    }
+    candidates
+}
+
+/// 返回决定模式的应用在 `candidates` 中的位置，`TopApp` 或没有可见应用时返回 `None`
+pub fn pick(aggregate: Aggregate, candidates: &[Candidate]) -> Option<usize> {
+    let indexed = || candidates.iter().enumerate();
+    match aggregate {
+        Aggregate::TopApp => None,
+        Aggregate::Max => indexed()
+            .max_by_key(|(index, candidate)| (candidate.rank, candidate.focused, Reverse(*index)))
+            .map(|(index, _)| index),
+        Aggregate::Weighted => {
+            let mut votes: HashMap<&Mode, u32> = HashMap::new();
+            for candidate in candidates {
+                *votes.entry(candidate.mode).or_default() += candidate.weight;
+            }
+            let (_, winner) = indexed().max_by_key(|(index, candidate)| {
+                (votes[candidate.mode], candidate.rank, Reverse(*index))
+            })?;
+            // 由得票的模式中权重最大的应用作为代表
+            indexed()
+                .filter(|(_, candidate)| candidate.mode == winner.mode)
+                .max_by_key(|(index, candidate)| {
+                    (candidate.weight, candidate.focused, Reverse(*index))
+                })
+                .map(|(index, _)| index)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(topapp: &str) -> ConfigData {
+        let context = format!(
+            "\"com.tencent.tmgp.sgame\" = \"fast\"\n\"com.tencent.mm\" = \"powersave\"\n\n\
+             [topapp]\n{topapp}\n\n\
+             [mode.gaming]\nrank = 9\nuclamp.background = {{ min = 0, max = 100 }}\n\
+             uclamp.foreground = {{ min = 0, max = 100 }}\nuclamp.top-app = {{ min = 0, max = 100 }}\n"
+        );
+        ConfigData::for_test(&context)
+    }
+
+    fn window(package: &str, mode: WindowingMode, focused: bool) -> VisibleWindow {
+        VisibleWindow {
+            package: package.into(),
+            kind: WindowType::Application,
+            mode,
+            display: 0,
+            focused,
+        }
+    }
+
+    fn chosen<'a>(config: &'a ConfigData, windows: &'a [VisibleWindow]) -> Option<&'a str> {
+        let candidates = candidates(config, windows);
+        pick(config.topapp.aggregate, &candidates).map(|index| candidates[index].package)
+    }
+
+    #[test]
+    fn max_picks_highest_rank_regardless_of_focus() {
+        let windows = [
+            window("com.tencent.tmgp.sgame", WindowingMode::MultiWindow, false),
+            window("com.tencent.mm", WindowingMode::MultiWindow, true),
+        ];
+        let max = config("aggregate = \"max\"");
+        assert_eq!(chosen(&max, &windows), Some("com.tencent.tmgp.sgame"));
+        // 未命中规则的应用按 on 参与比较
+        let windows = [
+            window("com.tencent.mm", WindowingMode::MultiWindow, true),
+            window("com.example.reader", WindowingMode::MultiWindow, false),
+        ];
+        assert_eq!(chosen(&max, &windows), Some("com.example.reader"));
+        assert_eq!(chosen(&config("aggregate = \"top-app\""), &windows), None);
+        assert_eq!(chosen(&max, &[]), None);
+    }
+
+    #[test]
+    fn weighted_votes_by_window_size_and_focus() {
+        let weighted = config("aggregate = \"weighted\"");
+        // 全屏 4 票对小窗 1 + 焦点 2 票
+        let windows = [
+            window("com.tencent.tmgp.sgame", WindowingMode::Freeform, true),
+            window("com.tencent.mm", WindowingMode::Fullscreen, false),
+        ];
+        assert_eq!(chosen(&weighted, &windows), Some("com.tencent.mm"));
+        // 分屏时各 2 票，焦点多 2 票
+        let windows = [
+            window("com.tencent.tmgp.sgame", WindowingMode::MultiWindow, false),
+            window("com.tencent.mm", WindowingMode::MultiWindow, true),
+        ];
+        assert_eq!(chosen(&weighted, &windows), Some("com.tencent.mm"));
+        // 票数相同时取 rank 高的模式
+        let unfocused = config("aggregate = \"weighted\"\nweight = { focused = 0 }");
+        assert_eq!(chosen(&unfocused, &windows), Some("com.tencent.tmgp.sgame"));
+    }
+
+    #[test]
+    fn ignored_packages_and_custom_rank() {
+        let config = config("aggregate = \"max\"\nignore = [\"com.tencent.tmgp.sgame\"]");
+        let windows = [
+            window("com.tencent.tmgp.sgame", WindowingMode::MultiWindow, false),
+            window("com.tencent.mm", WindowingMode::MultiWindow, true),
+        ];
+        assert_eq!(chosen(&config, &windows), Some("com.tencent.mm"));
+        assert_eq!(config.rank(&Mode::Custom("gaming".into())), 9);
+        assert_eq!(config.rank(&Mode::Fast), 3);
+    }
+}
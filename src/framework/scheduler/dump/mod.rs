@@ -16,4 +16,98 @@
 // with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
 
 pub mod power;
+pub mod source;
 pub mod topapps;
+pub mod window;
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use serde::Deserialize;
+    use toml::Table;
+
+    use crate::framework::ConfigData;
+
+    use super::{
+        power::Power,
+        source::Sources,
+        window::{Snapshot, VisibleWindow},
+    };
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "kebab-case", deny_unknown_fields)]
+    struct Expected {
+        topapp: String,
+        screen_on: bool,
+        /// 未填写时不检查
+        windows: Option<Vec<VisibleWindow>>,
+        /// 上面的 `topapp` 使用默认配置，这里按给定的配置再检查
+        #[serde(default)]
+        resolve: Vec<Resolve>,
+    }
+
+    #[derive(Clone, Debug, PartialEq, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Resolve {
+        /// `[topapp]` 表
+        topapp: Table,
+        /// `[app]` 表
+        #[serde(default)]
+        app: Table,
+        expect: String,
+    }
+
+    fn config(topapp: Table, app: &Table) -> ConfigData {
+        let mut tables = Table::new();
+        tables.insert("topapp".into(), topapp.into());
+        ConfigData::for_test(&format!("{app}\n{tables}"))
+    }
+
+    /// 见 `tests/fixtures/dumpsys/README.md`
+    #[test]
+    fn fixtures_parse_as_expected() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/dumpsys");
+        let mut dirs: Vec<_> = fs::read_dir(&root)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.is_dir())
+            .collect();
+        dirs.sort();
+        assert!(!dirs.is_empty());
+
+        let mut failures = Vec::new();
+        for dir in &dirs {
+            let expected = fs::read_to_string(dir.join("expected.toml")).unwrap();
+            let expected: Expected = toml::from_str(&expected).unwrap();
+            let mut sources = Sources::replay(dir).unwrap();
+            let snapshot = Snapshot::parse(&sources.window.dump().unwrap());
+            let resolve = expected
+                .resolve
+                .iter()
+                .map(|resolve| {
+                    let config = config(resolve.topapp.clone(), &resolve.app);
+                    Resolve {
+                        expect: snapshot.topapp(&config).to_string(),
+                        ..resolve.clone()
+                    }
+                })
+                .collect();
+            let actual = Expected {
+                topapp: snapshot
+                    .topapp(&config(Table::new(), &Table::new()))
+                    .to_string(),
+                screen_on: Power::parse_power(&sources.power.dump().unwrap()),
+                windows: expected.windows.as_ref().map(|_| snapshot.windows.clone()),
+                resolve,
+            };
+            if actual != expected {
+                failures.push(format!(
+                    "{}: 期望 {expected:?}，实际 {actual:?}",
+                    dir.file_name().unwrap().to_string_lossy()
+                ));
+            }
+        }
+        assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+    }
+}
@@ -20,9 +20,10 @@ use std::{
     time::{Duration, Instant},
 };
 
-use dumpsys_rs::Dumpsys;
 use regex::Regex;
 
+use super::source::Source;
+
 static WAKE_REGEX: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"mWakefulness=(Awake|Dreaming)").unwrap());
 static SCREEN_BLOCK_REGEX: LazyLock<Regex> =
@@ -35,48 +36,43 @@ static LEGACY_SCREEN_REGEX: LazyLock<Regex> =
 const RESET_TIME: Duration = Duration::from_secs(1);
 
 pub struct Power {
-    dumper: Dumpsys,
+    source: Box<dyn Source>,
     pub state: bool,
     time: Instant,
+    failing: bool,
 }
 
 impl Power {
-    pub fn new() -> Self {
-        let dumper = loop {
-            if let Some(dump) = Dumpsys::new("power") {
-                break dump;
-            }
-            log::error!("无法获取屏幕状态，正在重试");
-            std::thread::sleep(Duration::from_secs(1));
-        };
+    pub fn new(source: Box<dyn Source>) -> Self {
         Self {
-            dumper,
+            source,
             state: true,
             time: Instant::now(),
+            failing: false,
         }
     }
 
+    /// 获取失败时保留上一次的结果，下次调用时重试
     pub fn power_dumper(&mut self) {
         if self.time.elapsed() > RESET_TIME {
-            let dump = loop {
-                match self.dumper.dump(&["state"]) {
-                    Ok(dump) => break dump,
-                    Err(e) => {
-                        log::error!("无法获取屏幕状态：{e}，正在重试");
-                        std::thread::sleep(Duration::from_secs(1));
+            let dump = match self.source.dump() {
+                Ok(dump) => dump,
+                Err(e) => {
+                    if !self.failing {
+                        log::error!("无法获取屏幕状态：{e:#}，正在重试");
                     }
+                    self.failing = true;
+                    return;
                 }
             };
+            self.failing = false;
             self.state = Self::parse_power(&dump);
-            #[cfg(debug_assertions)]
-            {
-                log::debug!("当前屏幕状态 {}", self.state);
-            }
+            log::debug!(event = "screen", screen_on = self.state; "当前屏幕状态 {}", self.state);
         }
     }
 
     #[allow(clippy::pedantic)]
-    fn parse_power(output: &str) -> bool {
+    pub(super) fn parse_power(output: &str) -> bool {
         WAKE_REGEX.is_match(output)
             || SCREEN_BLOCK_REGEX.is_match(output)
             || LEGACY_SCREEN_REGEX.is_match(output)
@@ -0,0 +1,146 @@
+// Copyright 2023-2025, [rust@localhost] $ (@3532340532)
+//
+// This file is part of EfficientScheduler.
+//
+// EfficientScheduler is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// EfficientScheduler is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along
+// with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use anyhow::{Context, Result, bail};
+use dumpsys_rs::Dumpsys;
+
+/// 观测数据的来源，返回与 `dumpsys` 相同格式的文本
+pub trait Source {
+    fn dump(&mut self) -> Result<String>;
+}
+
+/// 在设备上调用 `dumpsys <service> <args>`
+pub struct DumpsysSource {
+    dumper: Dumpsys,
+    args: &'static [&'static str],
+}
+
+impl DumpsysSource {
+    /// 服务尚未启动时会一直等待
+    pub fn new(service: &str, args: &'static [&'static str]) -> Self {
+        let dumper = loop {
+            if let Some(dumper) = Dumpsys::new(service) {
+                break dumper;
+            }
+            log::error!("无法连接 dumpsys 服务 {service}，正在重试");
+            thread::sleep(Duration::from_secs(1));
+        };
+        Self { dumper, args }
+    }
+}
+
+impl Source for DumpsysSource {
+    fn dump(&mut self) -> Result<String> {
+        Ok(self.dumper.dump(self.args)?)
+    }
+}
+
+/// 从文件读取事先保存的 dumpsys 输出
+///
+/// 路径为文件时每次重新读取，修改文件即可模拟状态变化；
+/// 为目录时按文件名顺序每次读取一个，读完后停留在最后一个
+pub struct ReplaySource {
+    frames: Vec<PathBuf>,
+    next: usize,
+}
+
+impl ReplaySource {
+    pub fn new(path: &Path) -> Result<Self> {
+        let frames = if path.is_dir() {
+            let mut frames = fs::read_dir(path)
+                .with_context(|| format!("无法读取目录{}", path.display()))?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()?;
+            frames.retain(|frame| frame.is_file());
+            frames.sort();
+            frames
+        } else {
+            vec![path.to_path_buf()]
+        };
+        if frames.is_empty() {
+            bail!("{}中没有可回放的文件", path.display());
+        }
+        Ok(Self { frames, next: 0 })
+    }
+}
+
+impl Source for ReplaySource {
+    fn dump(&mut self) -> Result<String> {
+        let frame = &self.frames[self.next];
+        if self.next + 1 < self.frames.len() {
+            self.next += 1;
+        }
+        fs::read_to_string(frame).with_context(|| format!("无法读取{}", frame.display()))
+    }
+}
+
+/// 顶层应用与屏幕状态的来源
+pub struct Sources {
+    pub window: Box<dyn Source>,
+    pub power: Box<dyn Source>,
+}
+
+impl Sources {
+    pub fn dumpsys() -> Self {
+        Self {
+            window: Box::new(DumpsysSource::new("window", &["visible-apps"])),
+            power: Box::new(DumpsysSource::new("power", &["state"])),
+        }
+    }
+
+    /// 分别读取 `dir/window` 与 `dir/power`，二者均可为文件或目录
+    pub fn replay(dir: &Path) -> Result<Self> {
+        Ok(Self {
+            window: Box::new(ReplaySource::new(&dir.join("window"))?),
+            power: Box::new(ReplaySource::new(&dir.join("power"))?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_steps_through_frames_and_holds_the_last() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("002"), "b").unwrap();
+        fs::write(dir.path().join("001"), "a").unwrap();
+        let mut source = ReplaySource::new(dir.path()).unwrap();
+        let frames: Vec<_> = (0..3).map(|_| source.dump().unwrap()).collect();
+        assert_eq!(frames, ["a", "b", "b"]);
+
+        let file = dir.path().join("001");
+        let mut source = ReplaySource::new(&file).unwrap();
+        assert_eq!(source.dump().unwrap(), "a");
+        fs::write(&file, "c").unwrap();
+        assert_eq!(source.dump().unwrap(), "c");
+    }
+
+    #[test]
+    fn replay_rejects_empty_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(ReplaySource::new(dir.path()).is_err());
+    }
+}
@@ -15,86 +15,57 @@
 // You should have received a copy of the GNU General Public License along
 // with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
 
-use std::{
-    sync::LazyLock,
-    time::{Duration, Instant},
-};
+use std::time::{Duration, Instant};
 
-use dumpsys_rs::Dumpsys;
-use regex::Regex;
+use crate::framework::ConfigData;
 
-const RESET_TIME: Duration = Duration::from_secs(1);
+use super::{source::Source, window::Snapshot};
 
-static WINDOW_TYPES: &[(&str, &str)] = &[
-    ("overlay", r"type=APPLICATION_OVERLAY"),
-    ("freeform", r"windowingMode=5"),
-    ("pip", r"mPictureInPicture"),
-    ("dialog", r"type=APPLICATION_ABOVE_SUB_PANEL"),
-];
-static WINDOW_REGEX: LazyLock<Regex> = LazyLock::new(|| {
-    let patterns: Vec<&str> = WINDOW_TYPES.iter().map(|(_, p)| *p).collect();
-    Regex::new(&format!(
-        r"Window\{{.*?\s+([a-zA-Z0-9._]+)/.*?({})",
-        patterns.join("|")
-    ))
-    .unwrap()
-});
-static FOCUSED_REGEX: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"mCurrentFocus=Window\{.*?\s+([a-zA-Z0-9._]+)/").unwrap());
+const RESET_TIME: Duration = Duration::from_secs(1);
 
 pub struct TopAppsWatcher {
-    dumper: Dumpsys,
+    source: Box<dyn Source>,
     pub topapps: String,
+    /// 所有可见的应用窗口
+    pub snapshot: Snapshot,
     time: Instant,
+    failing: bool,
 }
 
 impl TopAppsWatcher {
-    pub fn new() -> Self {
-        let dumper = loop {
-            if let Some(dump) = Dumpsys::new("window") {
-                break dump;
-            }
-            log::error!("无法获取顶层应用，正在重试");
-            std::thread::sleep(Duration::from_secs(1));
-        };
+    pub fn new(source: Box<dyn Source>) -> Self {
         Self {
-            dumper,
+            source,
             topapps: String::new(),
+            snapshot: Snapshot::default(),
             time: Instant::now(),
+            failing: false,
         }
     }
 
-    pub fn topapp_dumper(&mut self) {
+    /// 获取失败时保留上一次的结果，下次调用时重试
+    pub fn topapp_dumper(&mut self, config: &ConfigData) {
         if self.time.elapsed() > RESET_TIME {
-            let dump = loop {
-                match self.dumper.dump(&["visible-apps"]) {
-                    Ok(dump) => break dump,
-                    Err(e) => {
-                        log::error!("无法获取顶层应用：{e}，正在重试");
-                        std::thread::sleep(Duration::from_secs(1));
+            let dump = match self.source.dump() {
+                Ok(dump) => dump,
+                Err(e) => {
+                    if !self.failing {
+                        log::error!("无法获取顶层应用：{e:#}，正在重试");
                     }
+                    self.failing = true;
+                    return;
                 }
             };
-            self.topapps = Self::parse_top_app(&dump);
-            #[cfg(debug_assertions)]
-            {
-                log::debug!("当前顶层应用 {}", self.topapps);
-            }
-        }
-    }
-
-    fn parse_top_app(dump: &str) -> String {
-        let mut windows: Vec<&str> = WINDOW_REGEX
-            .captures_iter(dump)
-            .filter_map(|c| c.get(1).map(|m| m.as_str()))
-            .collect();
-        if let Some(top_window) = windows.pop() {
-            return top_window.to_string();
+            self.failing = false;
+            self.snapshot = Snapshot::parse(&dump);
+            self.topapps = self.snapshot.topapp(config).to_string();
+            log::debug!(
+                event = "topapp",
+                package = self.topapps.as_str(),
+                windows = self.snapshot.windows.len();
+                "当前顶层应用 {}",
+                self.topapps
+            );
         }
-        FOCUSED_REGEX
-            .captures(dump)
-            .and_then(|c| c.get(1))
-            .map(|m| m.as_str().to_string())
-            .unwrap_or_default()
     }
 }
@@ -0,0 +1,278 @@
+// Copyright 2023-2025, [rust@localhost] $ (@3532340532)
+//
+// This file is part of EfficientScheduler.
+//
+// EfficientScheduler is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// EfficientScheduler is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along
+// with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{cmp::Reverse, sync::LazyLock};
+
+use regex::Regex;
+#[cfg(test)]
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::framework::{
+    ConfigData,
+    config::topapp::{TopAppConfig, TopAppPolicy},
+};
+
+static HEADER_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*Window #\d+ Window\{([0-9a-f]+) u\d+ ([^}]*)\}:").unwrap());
+static FOCUSED_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"mCurrentFocus=Window\{([0-9a-f]+) u\d+ ([a-zA-Z0-9._]+)/").unwrap()
+});
+static PACKAGE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\bpackage=([a-zA-Z0-9._]+)").unwrap());
+static DISPLAY_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\bmDisplayId=(\d+)").unwrap());
+static TYPE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(?:ty|type)=([A-Z_]+|\d+)").unwrap());
+static MODE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(?:mWindowingMode|windowingMode)=([a-z-]+|\d+)").unwrap());
+static PIP_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"mPictureInPicture(?:=true)?(?:\s|$)").unwrap());
+
+/// 窗口类型，来自 `ty=`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[cfg_attr(test, derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub enum WindowType {
+    /// 普通的应用窗口
+    Application,
+    /// 依附于应用窗口的面板与对话框
+    Dialog,
+    /// 悬浮窗
+    Overlay,
+}
+
+impl WindowType {
+    /// 系统窗口返回 `None`
+    fn parse(ty: &str) -> Option<Self> {
+        match ty {
+            "BASE_APPLICATION" | "APPLICATION" | "APPLICATION_STARTING" | "1" | "2" | "3" => {
+                Some(Self::Application)
+            }
+            "APPLICATION_OVERLAY" | "SYSTEM_ALERT" | "PHONE" | "2038" | "2003" | "2002" => {
+                Some(Self::Overlay)
+            }
+            ty if ty.starts_with("APPLICATION_") || (ty.starts_with("10") && ty.len() == 4) => {
+                Some(Self::Dialog)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// 窗口模式，来自窗口配置中的 `mWindowingMode=`，旧版本为数字
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[cfg_attr(test, derive(Deserialize))]
+#[serde(rename_all = "kebab-case")]
+pub enum WindowingMode {
+    #[default]
+    Undefined,
+    Fullscreen,
+    /// 画中画
+    Pinned,
+    /// 小窗
+    Freeform,
+    /// 分屏等多窗口，包括旧版本的 `split-screen-primary/secondary`
+    MultiWindow,
+}
+
+impl WindowingMode {
+    fn parse(mode: &str) -> Self {
+        match mode {
+            "fullscreen" | "1" => Self::Fullscreen,
+            "pinned" | "2" => Self::Pinned,
+            "freeform" | "5" => Self::Freeform,
+            "multi-window"
+            | "split-screen-primary"
+            | "split-screen-secondary"
+            | "3"
+            | "4"
+            | "6" => Self::MultiWindow,
+            _ => Self::Undefined,
+        }
+    }
+}
+
+/// 一个可见的应用窗口
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[cfg_attr(test, derive(Deserialize))]
+pub struct VisibleWindow {
+    pub package: String,
+    #[serde(rename = "type")]
+    pub kind: WindowType,
+    pub mode: WindowingMode,
+    pub display: u32,
+    pub focused: bool,
+}
+
+impl VisibleWindow {
+    /// 悬浮于其他应用之上，包括悬浮窗、对话框、小窗与画中画
+    pub const fn is_floating(&self) -> bool {
+        matches!(self.kind, WindowType::Overlay | WindowType::Dialog)
+            || matches!(self.mode, WindowingMode::Freeform | WindowingMode::Pinned)
+    }
+}
+
+/// `dumpsys window visible-apps` 的解析结果
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    /// 按 dumpsys 输出的顺序，即从最上层到最下层排列，系统窗口不包含在内；
+    /// 各策略中需要按位置取舍时都以靠上者优先
+    pub windows: Vec<VisibleWindow>,
+    /// 焦点窗口所属的包名，焦点不在应用窗口上时为空
+    pub focus: String,
+}
+
+impl Snapshot {
+    pub fn parse(dump: &str) -> Self {
+        let focus = FOCUSED_REGEX.captures(dump);
+        let focus_hash = focus.as_ref().map(|c| &c[1]);
+        let mut windows = Vec::new();
+        let mut lines = dump.lines().peekable();
+        while let Some(line) = lines.next() {
+            let Some(header) = HEADER_REGEX.captures(line) else {
+                continue;
+            };
+            // 窗口的详细信息缩进四格，到缩进更少的一行为止
+            let mut block = String::new();
+            while let Some(line) = lines.next_if(|line| line.starts_with("    ")) {
+                block.push_str(line);
+                block.push('\n');
+            }
+            let Some(kind) = TYPE_REGEX
+                .captures(&block)
+                .and_then(|c| WindowType::parse(&c[1]))
+            else {
+                continue;
+            };
+            let package = PACKAGE_REGEX.captures(&block).map_or_else(
+                || header[2].split('/').next().unwrap_or_default().to_string(),
+                |c| c[1].to_string(),
+            );
+            let mode = if PIP_REGEX.is_match(&block) {
+                WindowingMode::Pinned
+            } else {
+                MODE_REGEX
+                    .captures(&block)
+                    .map_or_else(WindowingMode::default, |c| WindowingMode::parse(&c[1]))
+            };
+            windows.push(VisibleWindow {
+                package,
+                kind,
+                mode,
+                display: DISPLAY_REGEX
+                    .captures(&block)
+                    .and_then(|c| c[1].parse().ok())
+                    .unwrap_or_default(),
+                focused: focus_hash == Some(&header[1]),
+            });
+        }
+        Self {
+            windows,
+            focus: focus.map(|c| c[2].to_string()).unwrap_or_default(),
+        }
+    }
+
+    /// 按 `[topapp]` 中的策略选出顶层应用，忽略列表中的包名不参与选择
+    pub fn topapp(&self, config: &ConfigData) -> &str {
+        let topapp = &config.topapp;
+        let mut candidates = self
+            .windows
+            .iter()
+            .filter(|window| !topapp.ignores(&window.package));
+        let chosen = match topapp.policy {
+            TopAppPolicy::Focused => None,
+            TopAppPolicy::Floating => candidates.find(|window| window.is_floating()),
+            TopAppPolicy::Priority => candidates
+                .enumerate()
+                .filter_map(|(index, window)| {
+                    let found = config.find_app(&window.package)?;
+                    let key = (
+                        found.kind,
+                        Reverse(found.pattern.len()),
+                        !window.focused,
+                        index,
+                    );
+                    Some((key, window))
+                })
+                .min_by_key(|(key, _)| *key)
+                .map(|(_, window)| window),
+        };
+        chosen.map_or_else(|| self.focused(topapp), |window| window.package.as_str())
+    }
+
+    /// 焦点窗口被忽略时，取最上层的未被忽略的非悬浮应用窗口；
+    /// 焦点不在应用窗口上（锁屏、切换动画等）时为空，与旧版本一致
+    fn focused(&self, topapp: &TopAppConfig) -> &str {
+        if self.focus.is_empty() || !topapp.ignores(&self.focus) {
+            return &self.focus;
+        }
+        self.windows
+            .iter()
+            .find(|window| {
+                window.kind == WindowType::Application
+                    && !window.is_floating()
+                    && !topapp.ignores(&window.package)
+            })
+            .map_or("", |window| window.package.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numeric_type_and_mode_from_older_versions() {
+        let dump = "\
+  Window #0 Window{a1 u0 com.example.float}:
+    mDisplayId=2 package=com.example.float
+    mAttrs=WM.LayoutParams{(0,0)(wrapxwrap) ty=2038 fl=#1000008}
+    mFullConfiguration={winConfig={ windowingMode=5 }}
+  Window #1 Window{b2 u0 com.android.systemui/StatusBar}:
+    mAttrs={(0,0)(fillx63) ty=STATUS_BAR}
+  Window #2 Window{c3 u0 com.example.video/.Player}:
+    mAttrs={(0,0)(fillxfill) ty=BASE_APPLICATION}
+    mPictureInPicture
+  mCurrentFocus=Window{c3 u0 com.example.video/com.example.video.Player}
+";
+        let snapshot = Snapshot::parse(dump);
+        assert_eq!(
+            snapshot.windows,
+            [
+                VisibleWindow {
+                    package: "com.example.float".into(),
+                    kind: WindowType::Overlay,
+                    mode: WindowingMode::Freeform,
+                    display: 2,
+                    focused: false,
+                },
+                VisibleWindow {
+                    package: "com.example.video".into(),
+                    kind: WindowType::Application,
+                    mode: WindowingMode::Pinned,
+                    display: 0,
+                    focused: true,
+                },
+            ]
+        );
+        assert_eq!(
+            snapshot.topapp(&ConfigData::for_test("")),
+            "com.example.video"
+        );
+    }
+}
@@ -0,0 +1,179 @@
+// Copyright 2023-2025, [rust@localhost] $ (@3532340532)
+//
+// This file is part of EfficientScheduler.
+//
+// EfficientScheduler is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// EfficientScheduler is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along
+// with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+
+use crate::framework::Mode;
+
+use super::{
+    policy::{DesiredState, SystemState},
+    rules::Reason,
+};
+
+/// 每行一条 JSON 记录的模式切换日志
+pub const JOURNAL_FILE: &str = "journal.jsonl";
+/// 超过此大小时把当前文件改名为 `.old` 并重新开始，磁盘占用不超过两倍
+const MAX_SIZE: u64 = 256 * 1024;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Entry {
+    pub time: String,
+    pub from: Option<Mode>,
+    pub to: Mode,
+    pub reason: String,
+    /// 命中的应用规则
+    pub rule: Option<String>,
+    pub topapp: String,
+    pub screen_on: bool,
+}
+
+impl Entry {
+    pub fn new(from: Option<Mode>, desired: &DesiredState, state: &SystemState) -> Self {
+        let rule = match &desired.reason {
+            Reason::App { pattern, .. }
+            | Reason::Visible {
+                rule: Some((_, pattern)),
+                ..
+            } => Some(pattern.clone()),
+            _ => None,
+        };
+        Self {
+            time: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            from,
+            to: desired.mode.clone(),
+            reason: desired.reason.to_string(),
+            rule,
+            topapp: state.topapp.clone(),
+            screen_on: state.screen_on,
+        }
+    }
+}
+
+pub struct Journal {
+    path: PathBuf,
+    limit: u64,
+    failed: bool,
+}
+
+impl Journal {
+    pub const fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            limit: MAX_SIZE,
+            failed: false,
+        }
+    }
+
+    /// 写入失败不影响调度，只记录一次错误
+    pub fn record(&mut self, entry: &Entry) {
+        match self.append(entry) {
+            Ok(()) => self.failed = false,
+            Err(e) => {
+                if !self.failed {
+                    log::error!("无法写入切换记录{}：{e:#}", self.path.display());
+                }
+                self.failed = true;
+            }
+        }
+    }
+
+    fn append(&self, entry: &Entry) -> Result<()> {
+        if fs::metadata(&self.path).is_ok_and(|meta| meta.len() >= self.limit) {
+            fs::rename(&self.path, old_path(&self.path))?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        Ok(())
+    }
+}
+
+fn old_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".old");
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+    use crate::framework::config::mode::Profile;
+
+    fn entry(mode: Mode) -> Entry {
+        let desired = DesiredState {
+            profile: Profile::builtin(&mode).unwrap(),
+            mode,
+            reason: Reason::App {
+                kind: crate::framework::config::matcher::MatchKind::Glob,
+                pattern: "com.example.*".into(),
+            },
+        };
+        let state = SystemState {
+            topapp: "com.example.game".into(),
+            windows: Vec::new(),
+            screen_on: true,
+            ..SystemState::default()
+        };
+        Entry::new(Some(Mode::Balance), &desired, &state)
+    }
+
+    #[test]
+    fn records_transition_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(JOURNAL_FILE);
+        let mut journal = Journal::new(path.clone());
+        journal.record(&entry(Mode::Fast));
+
+        let line = fs::read_to_string(&path).unwrap();
+        let record: Value = serde_json::from_str(line.trim()).unwrap();
+        assert_eq!(record["from"], "balance");
+        assert_eq!(record["to"], "fast");
+        assert_eq!(record["rule"], "com.example.*");
+        assert_eq!(record["topapp"], "com.example.game");
+        assert_eq!(record["screen_on"], true);
+    }
+
+    #[test]
+    fn rotates_when_full() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(JOURNAL_FILE);
+        let mut journal = Journal {
+            limit: 512,
+            ..Journal::new(path.clone())
+        };
+        for _ in 0..64 {
+            journal.record(&entry(Mode::Fast));
+        }
+        let current = fs::metadata(&path).unwrap().len();
+        let old = fs::metadata(old_path(&path)).unwrap().len();
+        assert!(current <= 512 + 256, "{current}");
+        assert!(old <= 512 + 256, "{old}");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+}
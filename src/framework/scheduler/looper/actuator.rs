@@ -0,0 +1,71 @@
+// Copyright 2023-2025, [rust@localhost] $ (@3532340532)
+//
+// This file is part of EfficientScheduler.
+//
+// EfficientScheduler is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// EfficientScheduler is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along
+// with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::framework::{
+    config::mode::Cpuctl,
+    scheduler::policy::{Actuator, DesiredState},
+};
+
+use super::{
+    buffer::Buffer,
+    cpu::{AppliedFreq, Cpu},
+};
+
+/// 最近一次写入内核的值
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Applied {
+    /// 以 `policyN` 为键，未配置过的 policy 不包含在内
+    pub freq: BTreeMap<String, AppliedFreq>,
+    pub uclamp: Option<Cpuctl>,
+}
+
+pub struct SysfsActuator {
+    cpu: Cpu,
+    buffer: Buffer,
+    applied: Applied,
+}
+
+impl SysfsActuator {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            cpu: Cpu::new()?,
+            buffer: Buffer::new(),
+            applied: Applied::default(),
+        })
+    }
+
+    pub const fn applied(&self) -> &Applied {
+        &self.applied
+    }
+}
+
+impl Actuator for SysfsActuator {
+    fn apply(&mut self, desired: &DesiredState) {
+        let freq = self.cpu.set_freqs(&desired.profile.freq);
+        self.buffer.match_uclamp(&desired.profile.uclamp);
+        self.applied.freq.extend(
+            freq.into_iter()
+                .map(|(policy, freq)| (format!("policy{policy}"), freq)),
+        );
+        self.applied.uclamp = Some(desired.profile.uclamp.clone());
+    }
+}
@@ -17,77 +17,29 @@
 
 #![allow(clippy::pedantic)]
 
-use std::{fs, os::unix::fs::PermissionsExt};
-
-use super::Mode;
-
-const PRECOMPUTED: [Cpuctl; 4] = [
-    Cpuctl {
-        bg_uclamp: Uclamp { max: 10, min: 5 },
-        ta_uclamp: Uclamp { max: 20, min: 10 },
-        fg_uclamp: Uclamp { max: 25, min: 20 },
-    },
-    Cpuctl {
-        bg_uclamp: Uclamp { max: 15, min: 10 },
-        ta_uclamp: Uclamp { max: 25, min: 20 },
-        fg_uclamp: Uclamp { max: 30, min: 20 },
-    },
-    Cpuctl {
-        bg_uclamp: Uclamp { max: 40, min: 10 },
-        ta_uclamp: Uclamp { max: 40, min: 20 },
-        fg_uclamp: Uclamp { max: 60, min: 20 },
-    },
-    Cpuctl {
-        bg_uclamp: Uclamp { max: 100, min: 10 },
-        ta_uclamp: Uclamp { max: 100, min: 20 },
-        fg_uclamp: Uclamp { max: 100, min: 20 },
-    },
-];
-
-#[derive(Clone)]
-struct Cpuctl {
-    bg_uclamp: Uclamp,
-    ta_uclamp: Uclamp,
-    fg_uclamp: Uclamp,
-}
-
-#[derive(Clone)]
-struct Uclamp {
-    max: usize,
-    min: usize,
-}
+use crate::framework::{
+    config::mode::{Cpuctl, Uclamp},
+    kernel,
+};
 
 pub struct Buffer {
     cpuctl: Cpuctl,
-    mode: Mode,
 }
 
 impl Buffer {
     pub const fn new() -> Self {
         Self {
-            mode: Mode::Balance,
             cpuctl: Cpuctl {
-                bg_uclamp: Uclamp { max: 0, min: 0 },
-                ta_uclamp: Uclamp { max: 0, min: 0 },
-                fg_uclamp: Uclamp { max: 0, min: 0 },
+                background: Uclamp { max: 0, min: 0 },
+                top_app: Uclamp { max: 0, min: 0 },
+                foreground: Uclamp { max: 0, min: 0 },
             },
         }
     }
 
     #[allow(clippy::nursery)]
-    pub fn set_mode(&mut self, mode: Mode) {
-        self.mode = mode;
-    }
-
-    #[allow(clippy::nursery)]
-    pub fn match_uclamp(&mut self) {
-        let index = match self.mode {
-            Mode::Powersave => 0,
-            Mode::Balance => 1,
-            Mode::Performance => 2,
-            Mode::Fast => 3,
-        };
-        self.cpuctl = PRECOMPUTED[index].clone();
+    pub fn match_uclamp(&mut self, cpuctl: &Cpuctl) {
+        self.cpuctl = cpuctl.clone();
         self.set_uclamp();
     }
 
@@ -95,35 +47,49 @@ impl Buffer {
         let operations = [
             (
                 "/dev/cpuctl/background/cpu.uclamp.max",
-                self.cpuctl.bg_uclamp.max,
+                self.cpuctl.background.max,
             ),
             (
                 "/dev/cpuctl/background/cpu.uclamp.min",
-                self.cpuctl.bg_uclamp.min,
+                self.cpuctl.background.min,
             ),
             (
                 "/dev/cpuctl/foreground/cpu.uclamp.max",
-                self.cpuctl.fg_uclamp.max,
+                self.cpuctl.foreground.max,
             ),
             (
                 "/dev/cpuctl/foreground/cpu.uclamp.min",
-                self.cpuctl.fg_uclamp.min,
+                self.cpuctl.foreground.min,
             ),
             (
                 "/dev/cpuctl/top-app/cpu.uclamp.max",
-                self.cpuctl.ta_uclamp.max,
+                self.cpuctl.top_app.max,
             ),
             (
                 "/dev/cpuctl/top-app/cpu.uclamp.min",
-                self.cpuctl.ta_uclamp.min,
+                self.cpuctl.top_app.min,
             ),
         ];
         for (path, value) in operations {
-            if let Err(e) = fs::set_permissions(path, fs::Permissions::from_mode(0o644)) {
-                log::error!("无法设置权限 {}: {}", path, e);
+            let path = kernel::path(path);
+            if let Err(e) = kernel::chmod(&path, 0o644) {
+                log::error!(
+                    event = "chmod_failed",
+                    path:% = path.display();
+                    "无法设置权限 {}: {}",
+                    path.display(),
+                    e
+                );
             }
-            if let Err(e) = fs::write(path, value.to_string()) {
-                log::error!("无法写入文件 {}: {}", path, e);
+            if let Err(e) = kernel::write(&path, &value.to_string()) {
+                log::error!(
+                    event = "write_failed",
+                    path:% = path.display(),
+                    value;
+                    "无法写入文件 {}: {}",
+                    path.display(),
+                    e
+                );
             }
         }
     }
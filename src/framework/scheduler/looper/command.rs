@@ -0,0 +1,136 @@
+// Copyright 2023-2025, [rust@localhost] $ (@3532340532)
+//
+// This file is part of EfficientScheduler.
+//
+// EfficientScheduler is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// EfficientScheduler is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along
+// with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
+
+use std::time::{Duration, Instant};
+
+use anyhow::{Result, bail};
+use serde_json::{Value, json};
+
+use super::Looper;
+use crate::framework::{
+    ConfigData, Mode,
+    control::Request,
+    kernel,
+    scheduler::{manual::Manual, rules},
+};
+
+impl Looper {
+    /// 处理控制接口的请求，会改变状态的请求在返回前立即重新决策
+    pub(super) fn execute(&mut self, request: Request) -> Result<Value> {
+        if self.expire_manual() {
+            self.apply();
+        }
+        match request {
+            Request::Status => {}
+            Request::SetMode {
+                mode,
+                duration,
+                until,
+            } => {
+                let mode = Mode::from(mode);
+                if !self.controller.config().mode.contains_key(&mode) {
+                    bail!("未定义的模式 `{mode}`");
+                }
+                let duration = duration.map(Duration::from_secs);
+                let Some(manual) = Manual::new(
+                    mode.clone(),
+                    duration,
+                    until,
+                    &self.topapps.topapps,
+                    self.power.state,
+                ) else {
+                    bail!("覆盖时长过长");
+                };
+                match duration {
+                    Some(duration) => log::info!(
+                        event = "manual",
+                        mode:% = mode,
+                        duration = duration.as_secs();
+                        "通过控制接口手动覆盖为{mode}模式，{}后到期",
+                        humantime::format_duration(duration)
+                    ),
+                    None => {
+                        log::info!(
+                            event = "manual",
+                            mode:% = mode;
+                            "通过控制接口手动覆盖为{mode}模式"
+                        );
+                    }
+                }
+                self.manual = Some(manual);
+                self.apply();
+            }
+            Request::ClearOverride => {
+                if self.manual.take().is_some() {
+                    log::info!(event = "manual_clear"; "已清除手动覆盖，交由规则决定");
+                }
+                self.apply();
+            }
+            Request::ReloadConfig => {
+                let config = ConfigData::load(&self.config_path)?;
+                self.set_config(config);
+                log::info!("已通过控制接口重新加载配置文件");
+                self.apply();
+            }
+            Request::GetConfig => {
+                let config = self.controller.config();
+                let mut modes: Vec<_> = config.mode.keys().map(Mode::to_string).collect();
+                modes.sort_unstable();
+                return Ok(json!({
+                    "path": self.config_path,
+                    "modes": modes,
+                    "source": config.source(),
+                }));
+            }
+            Request::Explain => {
+                let state = self.state();
+                return Ok(json!({
+                    "topapp": state.topapp,
+                    "screen_on": state.screen_on,
+                    "mode": self.controller.current().map(|desired| &desired.mode),
+                    "steps": rules::explain(self.controller.config(), &state),
+                }));
+            }
+            Request::Restore => {
+                log::info!("收到还原请求，正在还原内核节点");
+                kernel::snapshot::restore()?;
+                return Ok(Value::Null);
+            }
+        }
+        Ok(self.status())
+    }
+
+    pub(super) fn status(&self) -> Value {
+        let current = self.controller.current();
+        let applied = self.controller.actuator().applied();
+        json!({
+            "mode": current.map(|desired| &desired.mode),
+            "reason": current.map(|desired| desired.reason.to_string()),
+            "topapp": self.topapps.topapps,
+            "windows": self.topapps.snapshot.windows,
+            "screen_on": self.power.state,
+            "manual": self.manual.as_ref().map(|manual| json!({
+                "mode": manual.mode,
+                "remaining": manual.remaining(Instant::now()).map(|left| left.as_secs()),
+                "until": manual.until,
+            })),
+            "scene": self.scene,
+            "freq": applied.freq,
+            "uclamp": applied.uclamp,
+        })
+    }
+}
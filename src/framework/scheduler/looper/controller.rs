@@ -0,0 +1,207 @@
+// Copyright 2023-2025, [rust@localhost] $ (@3532340532)
+//
+// This file is part of EfficientScheduler.
+//
+// EfficientScheduler is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// EfficientScheduler is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along
+// with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::framework::{
+    ConfigData,
+    scheduler::policy::{Actuator, DesiredState, Policy, SystemState},
+};
+
+/// 决策与执行的核心，不涉及任何观测手段，便于在普通 Linux 上用假数据测试
+pub struct Controller<P, A> {
+    config: ConfigData,
+    policy: P,
+    actuator: A,
+    current: Option<DesiredState>,
+}
+
+impl<P: Policy, A: Actuator> Controller<P, A> {
+    pub const fn new(config: ConfigData, policy: P, actuator: A) -> Self {
+        Self {
+            config,
+            policy,
+            actuator,
+            current: None,
+        }
+    }
+
+    pub const fn config(&self) -> &ConfigData {
+        &self.config
+    }
+
+    pub const fn actuator(&self) -> &A {
+        &self.actuator
+    }
+
+    pub const fn current(&self) -> Option<&DesiredState> {
+        self.current.as_ref()
+    }
+
+    pub fn set_config(&mut self, config: ConfigData) {
+        self.config = config;
+    }
+
+    pub fn tick(&mut self, state: &SystemState) -> &DesiredState {
+        let desired = self.policy.decide(&self.config, state);
+        if self
+            .current
+            .as_ref()
+            .is_none_or(|current| current.mode != desired.mode)
+        {
+            log::info!(
+                event = "mode_change",
+                mode:% = desired.mode,
+                reason:% = desired.reason,
+                package = state.topapp.as_str();
+                "切换到{}模式，原因：{}，顶层应用 {}",
+                desired.mode,
+                desired.reason,
+                state.topapp
+            );
+        }
+        self.actuator.apply(&desired);
+        self.current.insert(desired)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::framework::{
+        Mode,
+        scheduler::{policy::RulePolicy, rules::Reason},
+    };
+
+    #[derive(Default)]
+    struct RecordingActuator {
+        applied: Vec<DesiredState>,
+    }
+
+    impl Actuator for RecordingActuator {
+        fn apply(&mut self, desired: &DesiredState) {
+            self.applied.push(desired.clone());
+        }
+    }
+
+    /// `[app]` 表及之后的内容
+    const CONFIG: &str = r#"
+"com.example.game" = "gaming"
+"com.example.*" = "performance"
+
+[mode.gaming]
+uclamp.background = { min = 0, max = 50 }
+uclamp.foreground = { min = 10, max = 80 }
+uclamp.top-app = { min = 40, max = 100 }
+"#;
+
+    fn state(topapp: &str, screen_on: bool) -> SystemState {
+        SystemState {
+            topapp: topapp.into(),
+            screen_on,
+            ..SystemState::default()
+        }
+    }
+
+    fn controller() -> Controller<RulePolicy, RecordingActuator> {
+        Controller::new(
+            ConfigData::for_test(CONFIG),
+            RulePolicy,
+            RecordingActuator::default(),
+        )
+    }
+
+    fn applied_modes(controller: &Controller<RulePolicy, RecordingActuator>) -> Vec<Mode> {
+        controller
+            .actuator
+            .applied
+            .iter()
+            .map(|desired| desired.mode.clone())
+            .collect()
+    }
+
+    #[test]
+    fn applies_decision_every_tick() {
+        let mut controller = controller();
+        controller.tick(&state("com.android.launcher", true));
+        controller.tick(&state("com.android.launcher", true));
+        controller.tick(&state("com.example.news", true));
+        controller.tick(&state("com.example.news", false));
+        assert_eq!(
+            applied_modes(&controller),
+            [
+                Mode::Balance,
+                Mode::Balance,
+                Mode::Performance,
+                Mode::Powersave
+            ]
+        );
+    }
+
+    #[test]
+    fn staying_on_matched_app_keeps_its_mode() {
+        let mut controller = controller();
+        for _ in 0..3 {
+            let desired = controller.tick(&state("com.example.game", true));
+            assert_eq!(desired.mode, Mode::Custom("gaming".into()));
+        }
+    }
+
+    #[test]
+    fn custom_profile_reaches_actuator() {
+        let mut controller = controller();
+        controller.tick(&state("com.example.game", true));
+        let applied = &controller.actuator.applied[0];
+        assert_eq!(applied.profile.uclamp.top_app.min, 40);
+        assert_eq!(applied.profile.uclamp.top_app.max, 100);
+        assert_eq!(applied.profile.uclamp.background.max, 50);
+    }
+
+    #[test]
+    fn reloaded_config_takes_effect_on_next_tick() {
+        let mut controller = controller();
+        controller.tick(&state("com.android.launcher", true));
+        let config = "on = \"fast\"\noff = \"powersave\"\n\n[app]\n";
+        controller.set_config(ConfigData::parse(Path::new("test.toml"), config).unwrap());
+        let desired = controller.tick(&state("com.android.launcher", true));
+        assert_eq!(desired.mode, Mode::Fast);
+        assert_eq!(desired.reason, Reason::Default);
+    }
+
+    #[test]
+    fn policy_can_be_replaced() {
+        struct Always(Mode);
+
+        impl Policy for Always {
+            fn decide(&self, config: &ConfigData, _: &SystemState) -> DesiredState {
+                DesiredState {
+                    mode: self.0.clone(),
+                    reason: Reason::Default,
+                    profile: config.profile(&self.0).clone(),
+                }
+            }
+        }
+
+        let mut controller = Controller::new(
+            ConfigData::for_test(CONFIG),
+            Always(Mode::Fast),
+            RecordingActuator::default(),
+        );
+        controller.tick(&state("com.example.game", false));
+        assert_eq!(controller.actuator.applied[0].mode, Mode::Fast);
+    }
+}
@@ -16,15 +16,25 @@
 // with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
 
 use std::{
-    collections::HashMap,
-    fs::{self, write},
-    os::unix::fs::PermissionsExt,
-    path::{Path, PathBuf},
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::PathBuf,
 };
 
 use anyhow::Result;
+use serde::Serialize;
 
-use super::Mode;
+use crate::framework::{
+    config::mode::{FreqRange, PolicyKey},
+    kernel,
+};
+
+/// 实际写入的频率，单位 kHz
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct AppliedFreq {
+    pub max: isize,
+    pub min: isize,
+}
 
 pub struct Cpu {
     info: HashMap<usize, PathBuf>,
@@ -32,7 +42,7 @@ pub struct Cpu {
 
 impl Cpu {
     pub fn new() -> Result<Self> {
-        let sysfs = Path::new("/sys/devices/system/cpu/cpufreq/");
+        let sysfs = kernel::path("/sys/devices/system/cpu/cpufreq/");
         let mut info = HashMap::new();
         for entry in fs::read_dir(sysfs)? {
             let entry = entry?;
@@ -48,63 +58,101 @@ impl Cpu {
         Ok(Self { info })
     }
 
-    pub fn set_freqs(&self, mode: Mode) {
-        for (policy, path) in self.info.clone() {
+    /// 返回各 policy 解析后的频率，未配置的 policy 不包含在内
+    pub fn set_freqs(&self, freq: &HashMap<PolicyKey, FreqRange>) -> BTreeMap<usize, AppliedFreq> {
+        let mut applied = BTreeMap::new();
+        for (policy, path) in &self.info {
+            let Some(range) = freq
+                .get(&PolicyKey::Policy(*policy))
+                .or_else(|| freq.get(&PolicyKey::Default))
+            else {
+                continue;
+            };
             let freq_max_path = path.join("scaling_max_freq");
-            let freq_min_path = path.join("scaling_mim_freq");
-            let freqs = fs::read_to_string(
-                path.join("scaling_available_frequencies")
-                    .to_string_lossy()
-                    .to_string()
-                    .as_str(),
-            )
-            .unwrap();
-            let context: Vec<isize> = freqs
+            let freq_min_path = path.join("scaling_min_freq");
+            let freqs = match fs::read_to_string(path.join("scaling_available_frequencies")) {
+                Ok(freqs) => freqs,
+                Err(e) => {
+                    log::error!("无法读取可用频率{}: {e}", path.display());
+                    continue;
+                }
+            };
+            let mut context: Vec<isize> = freqs
                 .split_whitespace()
                 .filter_map(|s| s.parse::<isize>().ok())
                 .collect();
-            let max_freq: isize;
-            let min_freq: isize;
-            match mode {
-                Mode::Powersave => {
-                    max_freq = context[context.len() - 5];
-                    min_freq = context[context.len() - 3];
-                }
-                Mode::Balance => {
-                    max_freq = context[5];
-                    min_freq = context[context.len() - 6];
-                }
-                Mode::Performance => {
-                    max_freq = context[1];
-                    min_freq = context[context.len() - 6];
-                }
-                Mode::Fast => {
-                    max_freq = context[1];
-                    min_freq = context[1];
-                }
-            }
-            if let Err(e) = fs::set_permissions(&freq_max_path, fs::Permissions::from_mode(0o644)) {
-                log::error!("无法设置权限{}: {e}", path.display());
-            }
-            if let Err(e) = fs::set_permissions(&freq_min_path, fs::Permissions::from_mode(0o644)) {
-                log::error!("无法设置权限{}: {e}", path.display());
+            context.sort_unstable_by(|a, b| b.cmp(a));
+            let (Some(max_freq), Some(min_freq)) =
+                (range.max.resolve(&context), range.min.resolve(&context))
+            else {
+                log::error!("可用频率为空{}", path.display());
+                continue;
+            };
+            if let Err(e) = kernel::chmod(&freq_max_path, 0o644) {
+                log::error!(
+                    event = "chmod_failed",
+                    path:% = freq_max_path.display();
+                    "无法设置权限{}: {e}",
+                    freq_max_path.display()
+                );
             }
-            if let Err(e) = write(&path, max_freq.to_string().as_bytes()) {
-                log::error!("无法写入频率{}: {e}", freq_max_path.display());
+            if let Err(e) = kernel::chmod(&freq_min_path, 0o644) {
+                log::error!(
+                    event = "chmod_failed",
+                    path:% = freq_min_path.display();
+                    "无法设置权限{}: {e}",
+                    freq_min_path.display()
+                );
             }
-            if let Err(e) = write(&path, min_freq.to_string().as_bytes()) {
-                log::error!("无法写入频率{}: {e}", freq_min_path.display());
+            if let Err(e) = kernel::write(&freq_max_path, &max_freq.to_string()) {
+                log::error!(
+                    event = "write_failed",
+                    path:% = freq_max_path.display(),
+                    value = max_freq;
+                    "无法写入频率{}: {e}",
+                    freq_max_path.display()
+                );
             }
-            if let Err(e) = fs::set_permissions(&freq_max_path, fs::Permissions::from_mode(0o444)) {
-                log::error!("无法设置权限{}: {e}", path.display());
+            if let Err(e) = kernel::write(&freq_min_path, &min_freq.to_string()) {
+                log::error!(
+                    event = "write_failed",
+                    path:% = freq_min_path.display(),
+                    value = min_freq;
+                    "无法写入频率{}: {e}",
+                    freq_min_path.display()
+                );
             }
-            if let Err(e) = fs::set_permissions(&freq_min_path, fs::Permissions::from_mode(0o444)) {
-                log::error!("无法设置权限{}: {e}", path.display());
+            if let Err(e) = kernel::chmod(&freq_max_path, 0o444) {
+                log::error!(
+                    event = "chmod_failed",
+                    path:% = freq_max_path.display();
+                    "无法设置权限{}: {e}",
+                    freq_max_path.display()
+                );
             }
-            #[cfg(debug_assertions)]
-            {
-                log::debug!("已为{policy}设置频率");
+            if let Err(e) = kernel::chmod(&freq_min_path, 0o444) {
+                log::error!(
+                    event = "chmod_failed",
+                    path:% = freq_min_path.display();
+                    "无法设置权限{}: {e}",
+                    freq_min_path.display()
+                );
             }
+            log::debug!(
+                event = "freq",
+                policy = *policy,
+                max = max_freq,
+                min = min_freq;
+                "已为{policy}设置频率"
+            );
+            applied.insert(
+                *policy,
+                AppliedFreq {
+                    max: max_freq,
+                    min: min_freq,
+                },
+            );
         }
+        applied
     }
 }
@@ -15,124 +15,220 @@
 // You should have received a copy of the GNU General Public License along
 // with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
 
+mod actuator;
 mod buffer;
+mod command;
+mod controller;
 mod cpu;
 
 use std::{
     collections::VecDeque,
-    ffi::CString,
-    fs,
-    os::unix::fs::PermissionsExt,
-    ptr,
+    path::PathBuf,
     sync::{
         Arc,
         atomic::{AtomicBool, Ordering},
+        mpsc::{Receiver, RecvTimeoutError, Sender},
     },
     thread,
+    time::{Duration, Instant},
 };
 
+use actuator::SysfsActuator;
 use anyhow::Result;
-use buffer::Buffer;
+use controller::Controller;
 use cpu::Cpu;
 use frame_analyzer::Analyzer;
-use libc::{MS_BIND, MS_REC, mount, umount, umount2};
 
-use crate::framework::ConfigData;
+use crate::framework::{
+    ConfigData, Mode,
+    control::{Request, Response},
+    kernel,
+};
 
-use super::dump::{power::Power, topapps::TopAppsWatcher};
+use super::{
+    dump::{power::Power, source::Sources, topapps::TopAppsWatcher},
+    journal::{Entry, Journal},
+    manual::Manual,
+    policy::{RulePolicy, SystemState},
+    scene,
+    status::StatusFile,
+};
 
-#[derive(Clone, Copy)]
-pub enum Mode {
-    Powersave,
-    Balance,
-    Performance,
-    Fast,
-}
+/// 各厂商自带的调频/游戏优化开关，不存在的节点会被跳过
+const DISABLE: [(&str, &str); 7] = [
+    ("/sys/module/mtk_fpsgo/parameters/perfmgr_enable", "0"),
+    ("/sys/module/perfmgr/parameters/perfmgr_enable", "0"),
+    ("/sys/module/perfmgr_policy/parameters/perfmgr_enable", "0"),
+    ("/sys/module/perfmgr_mtk/parameters/perfmgr_enable", "0"),
+    ("/sys/module/migt/parameters/glk_fbreak_enable", "0"),
+    ("/sys/module/migt/parameters/glk_disable", "1"),
+    ("/proc/game_opt/disable_cpufreq_limit", "1"),
+];
 
-struct Last {
-    topapp: Option<String>,
+pub enum Event {
+    Config(Box<ConfigData>),
+    /// `None` 表示交由规则决定
+    Scene(Option<Mode>),
+    /// 来自控制接口的请求，处理结果通过 `Sender` 返回
+    Control(Request, Sender<Response>),
 }
 
 pub struct Looper {
     topapps: TopAppsWatcher,
     power: Power,
-    config: ConfigData,
-    last: Last,
-    cpu: Cpu,
-    mode: Mode,
-    buffer: Buffer,
+    events: Receiver<Event>,
+    config_path: PathBuf,
+    manual: Option<Manual>,
+    scene: Option<Mode>,
+    controller: Controller<RulePolicy, SysfsActuator>,
+    status_file: StatusFile,
+    journal: Journal,
 }
 
 impl Looper {
-    pub fn new(config: ConfigData) -> Self {
+    pub fn new(
+        config_path: PathBuf,
+        config: ConfigData,
+        sources: Sources,
+        events: Receiver<Event>,
+        status_file: StatusFile,
+        journal: Journal,
+    ) -> Self {
         Self {
-            topapps: TopAppsWatcher::new(),
-            power: Power::new(),
-            config,
-            cpu: Cpu::new().unwrap(),
-            mode: Mode::Balance,
-            buffer: Buffer::new(),
-            last: Last { topapp: None },
+            topapps: TopAppsWatcher::new(sources.window),
+            power: Power::new(sources.power),
+            events,
+            config_path,
+            manual: None,
+            scene: None,
+            controller: Controller::new(config, RulePolicy, SysfsActuator::new().unwrap()),
+            status_file,
+            journal,
         }
     }
 
-    fn disable() -> Result<()> {
-        lock_value("/sys/module/mtk_fpsgo/parameters/perfmgr_enable", "0")?;
-        lock_value("/sys/module/perfmgr/parameters/perfmgr_enable", "0")?;
-        lock_value("/sys/module/perfmgr_policy/parameters/perfmgr_enable", "0")?;
-        lock_value("/sys/module/perfmgr_mtk/parameters/perfmgr_enable", "0")?;
-        lock_value("/sys/module/migt/parameters/glk_fbreak_enable", "0")?;
-        lock_value("/sys/module/migt/parameters/glk_disable", "1")?;
-        lock_value("/proc/game_opt/disable_cpufreq_limit", "1")?;
-        Ok(())
+    fn disable() {
+        for (path, value) in DISABLE {
+            let path = kernel::path(path);
+            if !path.exists() {
+                continue;
+            }
+            if let Err(e) = kernel::lock_value(&path, value) {
+                log::error!("无法锁定{}: {e}", path.display());
+            }
+        }
     }
 
     pub fn enter_looper(&mut self) {
-        let _ = Self::disable();
-        #[cfg(debug_assertions)]
-        {
-            log::debug!("已关闭大部分系统自带功能");
-        }
+        Self::disable();
+        log::debug!("已关闭大部分系统自带功能");
         let _ = self.try_boost_run();
         loop {
-            self.topapps.topapp_dumper();
+            self.topapps.topapp_dumper(self.controller.config());
             self.power.power_dumper();
-            if self.power.state {
-                for (app, mode) in self.config.app.clone() {
-                    if self.last.topapp.clone().unwrap_or_default() != self.topapps.topapps
-                        && self.topapps.topapps == app
-                    {
-                        match mode.as_str() {
-                            "powersave" => self.mode = Mode::Powersave,
-                            "balance" => self.mode = Mode::Balance,
-                            "performance" => self.mode = Mode::Performance,
-                            "fast" => self.mode = Mode::Fast,
-                            _ => log::error!("无效的Mode"),
-                        }
-                        self.last.topapp = Some(self.topapps.topapps.clone());
-                    } else {
-                        match self.config.on.as_str() {
-                            "powersave" => self.mode = Mode::Powersave,
-                            "balance" => self.mode = Mode::Balance,
-                            "performance" => self.mode = Mode::Performance,
-                            "fast" => self.mode = Mode::Fast,
-                            _ => log::error!("无效的Mode"),
-                        }
+            while let Ok(event) = self.events.try_recv() {
+                self.handle_event(event);
+            }
+            self.expire_manual();
+            self.apply();
+            self.wait_events(Duration::from_secs(1));
+        }
+    }
+
+    /// 返回覆盖是否结束
+    fn expire_manual(&mut self) -> bool {
+        let Some(manual) = &mut self.manual else {
+            return false;
+        };
+        let Some(end) = manual.check(Instant::now(), &self.topapps.topapps, self.power.state)
+        else {
+            return false;
+        };
+        log::info!(event = "manual_clear", reason:% = end; "手动覆盖{end}，交由规则决定");
+        self.manual = None;
+        true
+    }
+
+    fn set_config(&mut self, config: ConfigData) {
+        let old = &self.controller.config().log;
+        if (config.log.level, &config.log.filters) != (old.level, &old.filters) {
+            crate::logger::set_filters(&config.log);
+            log::info!("已更新日志级别");
+        }
+        if !config.log.same_output(old) {
+            log::warn!("日志输出位置的修改需要重启后生效");
+        }
+        self.controller.set_config(config);
+    }
+
+    fn state(&self) -> SystemState {
+        SystemState {
+            topapp: self.topapps.topapps.clone(),
+            windows: self.topapps.snapshot.windows.clone(),
+            screen_on: self.power.state,
+            manual: self.manual.as_ref().map(|manual| manual.mode.clone()),
+            scene: self.scene.clone(),
+        }
+    }
+
+    fn apply(&mut self) {
+        // 内核节点已还原，进程即将退出，不再决策也不再报告未写入的值
+        if kernel::snapshot::restored() {
+            return;
+        }
+        let state = self.state();
+        let previous = self
+            .controller
+            .current()
+            .map(|current| current.mode.clone());
+        let desired = self.controller.tick(&state);
+        if previous.as_ref() != Some(&desired.mode) {
+            self.journal.record(&Entry::new(previous, desired, &state));
+        }
+        let status = self.status();
+        self.status_file.update(&status);
+    }
+
+    /// 代替固定的休眠，使控制请求和文件变化能立即生效
+    fn wait_events(&mut self, interval: Duration) {
+        let deadline = Instant::now() + interval;
+        while let Some(timeout) = deadline.checked_duration_since(Instant::now()) {
+            match self.events.recv_timeout(timeout) {
+                Ok(event) => {
+                    if self.handle_event(event) {
+                        self.apply();
                     }
                 }
-            } else {
-                match self.config.off.as_str() {
-                    "powersave" => self.mode = Mode::Powersave,
-                    "balance" => self.mode = Mode::Balance,
-                    "performance" => self.mode = Mode::Performance,
-                    "fast" => self.mode = Mode::Fast,
-                    _ => log::error!("无效的Mode"),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => {
+                    thread::sleep(timeout);
+                    break;
                 }
             }
-            let () = self.cpu.set_freqs(self.mode);
-            self.buffer.set_mode(self.mode);
-            self.buffer.match_uclamp();
-            std::thread::sleep(std::time::Duration::from_secs(1));
+        }
+    }
+
+    /// 返回是否需要重新决策
+    fn handle_event(&mut self, event: Event) -> bool {
+        match event {
+            Event::Config(config) => {
+                self.set_config(*config);
+                log::info!(
+                    event = "config_reload",
+                    path:% = self.config_path.display();
+                    "已重新加载配置文件"
+                );
+                true
+            }
+            Event::Scene(scene) => scene::update(&mut self.scene, self.controller.config(), scene),
+            Event::Control(request, reply) => {
+                let response = match self.execute(request) {
+                    Ok(data) => Response::ok(data),
+                    Err(e) => Response::error(format!("{e:#}")),
+                };
+                let _ = reply.send(response);
+                false
+            }
         }
     }
 
@@ -141,6 +237,7 @@ impl Looper {
         analyzer.attach_app(Self::find_pid(self.topapps.topapps.as_str())? as i32)?;
         let running = Arc::new(AtomicBool::new(true));
         let mut buffer = VecDeque::with_capacity(120);
+        let freq = self.controller.config().profile(&Mode::Fast).freq.clone();
         thread::spawn(move || {
             let cpu = Cpu::new().unwrap();
             while running.load(Ordering::Acquire) {
@@ -150,7 +247,7 @@ impl Looper {
                         buffer.push_front(frametime);
                     }
                     if buffer.len() <= 10 {
-                        cpu.set_freqs(Mode::Fast);
+                        cpu.set_freqs(&freq);
                     }
                 }
             }
@@ -164,62 +261,13 @@ impl Looper {
                 let pid_str = entry.file_name().into_string().ok().unwrap_or_default();
                 let pid = pid_str.parse::<u32>()?;
                 let cmdline_path = format!("/proc/{pid}/cmdline");
-                if let Ok(cmdline) = std::fs::read_to_string(cmdline_path) {
-                    if cmdline.trim_matches('\0').contains(package_name) {
-                        return Ok(pid);
-                    }
+                if let Ok(cmdline) = std::fs::read_to_string(cmdline_path)
+                    && cmdline.trim_matches('\0').contains(package_name)
+                {
+                    return Ok(pid);
                 }
             }
         }
         Ok(0)
     }
 }
-
-pub fn lock_value(path: &str, value: &str) -> Result<()> {
-    let mount_path = format!("/cache/mount_mask_{value}");
-    unmount(path)?;
-    if let Err(e) = fs::set_permissions(path, fs::Permissions::from_mode(0o644)) {
-        log::error!("无法设置权限{}: {e}", path);
-    }
-    if let Err(e) = fs::write(path, value) {
-        log::error!("无法写入文件{}: {e}", path);
-    }
-    if let Err(e) = fs::set_permissions(path, fs::Permissions::from_mode(0o444)) {
-        log::error!("无法设置权限{}: {e}", path);
-    }
-    if let Err(e) = fs::write(&mount_path, value) {
-        log::error!("无法写入文件{}: {e}", mount_path);
-    }
-    mount_bind(&mount_path, path)?;
-    Ok(())
-}
-
-fn mount_bind(src_path: &str, dest_path: &str) -> Result<()> {
-    let src_path = CString::new(src_path)?;
-    let dest_path = CString::new(dest_path)?;
-
-    unsafe {
-        umount2(dest_path.as_ptr(), libc::MNT_DETACH);
-
-        if mount(
-            src_path.as_ptr().cast(),
-            dest_path.as_ptr().cast(),
-            ptr::null(),
-            MS_BIND | MS_REC,
-            ptr::null(),
-        ) != 0
-        {
-            return Err(std::io::Error::last_os_error().into());
-        }
-    }
-
-    Ok(())
-}
-
-fn unmount(file_system: &str) -> Result<()> {
-    let path = CString::new(file_system)?;
-    if unsafe { umount(path.as_ptr()) } != 0 {
-        return Err(std::io::Error::last_os_error().into());
-    }
-    Ok(())
-}
@@ -0,0 +1,210 @@
+// Copyright 2023-2025, [rust@localhost] $ (@3532340532)
+//
+// This file is part of EfficientScheduler.
+//
+// EfficientScheduler is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// EfficientScheduler is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along
+// with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::framework::Mode;
+
+/// 除到期外，额外结束覆盖的条件
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Until {
+    /// 顶层应用不再是设置覆盖时的应用；下拉通知栏、切换应用的动画中
+    /// 顶层应用会短暂为空，不算作变化
+    TopappChange,
+    /// 屏幕熄灭，设置时已息屏则等到下一次亮屏后再熄灭
+    ScreenOff,
+}
+
+/// 覆盖结束的原因
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum End {
+    Expired,
+    TopappChanged,
+    ScreenOff,
+}
+
+impl fmt::Display for End {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Expired => write!(f, "已到期"),
+            Self::TopappChanged => write!(f, "顶层应用已变化"),
+            Self::ScreenOff => write!(f, "已息屏"),
+        }
+    }
+}
+
+/// 通过控制接口设置的手动覆盖
+#[derive(Clone, Debug)]
+pub struct Manual {
+    pub mode: Mode,
+    /// `None` 表示一直有效，直到被清除
+    pub expires: Option<Instant>,
+    pub until: Option<Until>,
+    /// 设置覆盖时的顶层应用，设置时为空则取之后第一个非空的顶层应用
+    topapp: String,
+    screen_was_on: bool,
+}
+
+impl Manual {
+    /// 时长来自控制接口，到期时间溢出时返回 `None`
+    pub fn new(
+        mode: Mode,
+        duration: Option<Duration>,
+        until: Option<Until>,
+        topapp: &str,
+        screen_on: bool,
+    ) -> Option<Self> {
+        let expires = match duration {
+            Some(duration) => Some(Instant::now().checked_add(duration)?),
+            None => None,
+        };
+        Some(Self {
+            mode,
+            expires,
+            until,
+            topapp: topapp.to_string(),
+            screen_was_on: screen_on,
+        })
+    }
+
+    pub fn remaining(&self, now: Instant) -> Option<Duration> {
+        self.expires
+            .map(|expires| expires.saturating_duration_since(now))
+    }
+
+    /// 根据最新观测到的状态判断覆盖是否应结束
+    pub fn check(&mut self, now: Instant, topapp: &str, screen_on: bool) -> Option<End> {
+        if self.expires.is_some_and(|expires| expires <= now) {
+            return Some(End::Expired);
+        }
+        match self.until? {
+            Until::TopappChange if topapp.is_empty() => None,
+            Until::TopappChange if self.topapp.is_empty() => {
+                self.topapp = topapp.to_string();
+                None
+            }
+            Until::TopappChange if topapp != self.topapp => Some(End::TopappChanged),
+            Until::ScreenOff if !screen_on && self.screen_was_on => Some(End::ScreenOff),
+            Until::ScreenOff => {
+                self.screen_was_on |= screen_on;
+                None
+            }
+            Until::TopappChange => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAME: &str = "com.example.game";
+
+    #[test]
+    fn expires_after_duration() {
+        let now = Instant::now();
+        let mut manual =
+            Manual::new(Mode::Fast, Some(Duration::from_mins(1)), None, GAME, true).unwrap();
+        assert_eq!(manual.check(now, GAME, true), None);
+        assert_eq!(
+            manual.check(now + Duration::from_mins(2), GAME, true),
+            Some(End::Expired)
+        );
+        assert_eq!(
+            manual.remaining(now + Duration::from_mins(2)),
+            Some(Duration::ZERO)
+        );
+
+        let mut manual = Manual::new(Mode::Fast, None, None, GAME, true).unwrap();
+        assert_eq!(
+            manual.check(now + Duration::from_hours(24), "", false),
+            None
+        );
+        assert_eq!(manual.remaining(now), None);
+    }
+
+    #[test]
+    fn ends_when_topapp_changes() {
+        let now = Instant::now();
+        let mut manual =
+            Manual::new(Mode::Fast, None, Some(Until::TopappChange), GAME, true).unwrap();
+        assert_eq!(manual.check(now, GAME, false), None);
+        assert_eq!(
+            manual.check(now, "com.example.launcher", true),
+            Some(End::TopappChanged)
+        );
+    }
+
+    #[test]
+    fn empty_topapp_is_not_a_change() {
+        let now = Instant::now();
+        let mut manual =
+            Manual::new(Mode::Fast, None, Some(Until::TopappChange), GAME, true).unwrap();
+        // 下拉通知栏时顶层应用为空
+        assert_eq!(manual.check(now, "", true), None);
+        assert_eq!(manual.check(now, GAME, true), None);
+
+        let mut manual =
+            Manual::new(Mode::Fast, None, Some(Until::TopappChange), "", true).unwrap();
+        assert_eq!(manual.check(now, GAME, true), None);
+        assert_eq!(manual.check(now, "", true), None);
+        assert_eq!(
+            manual.check(now, "com.example.launcher", true),
+            Some(End::TopappChanged)
+        );
+    }
+
+    #[test]
+    fn ends_on_screen_off_after_being_on() {
+        let now = Instant::now();
+        let mut manual =
+            Manual::new(Mode::Powersave, None, Some(Until::ScreenOff), GAME, false).unwrap();
+        assert_eq!(manual.check(now, GAME, false), None);
+        assert_eq!(manual.check(now, GAME, true), None);
+        assert_eq!(manual.check(now, GAME, false), Some(End::ScreenOff));
+    }
+
+    #[test]
+    fn ttl_applies_together_with_condition() {
+        let now = Instant::now();
+        let mut manual = Manual::new(
+            Mode::Fast,
+            Some(Duration::from_mins(10)),
+            Some(Until::TopappChange),
+            GAME,
+            true,
+        )
+        .unwrap();
+        assert_eq!(manual.check(now, GAME, true), None);
+        assert_eq!(
+            manual.check(now + Duration::from_mins(11), GAME, true),
+            Some(End::Expired)
+        );
+    }
+
+    #[test]
+    fn overflowing_duration_is_rejected() {
+        let duration = Some(Duration::from_secs(u64::MAX));
+        assert!(Manual::new(Mode::Fast, duration, None, GAME, true).is_none());
+    }
+}
@@ -15,24 +15,77 @@
 // You should have received a copy of the GNU General Public License along
 // with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
 
-use std::fs::read_to_string;
+use std::{path::Path, sync::mpsc};
 
 use anyhow::Result;
 
-use super::ConfigData;
+use super::{ConfigData, config::path::module_dir, control, watcher};
+use dump::source::Sources;
+use looper::Event;
 
+pub mod aggregate;
 pub mod dump;
+pub mod journal;
 pub mod looper;
+pub mod manual;
+pub mod policy;
+pub mod rules;
+pub mod scene;
+pub mod status;
 
 pub struct Scheduler;
 
 impl Scheduler {
-    pub fn try_start_scheduler() -> Result<()> {
-        let context = read_to_string(
-            "/data/data/com.termux/files/home/.local/share/tmoe-linux/containers/chroot/arch_arm64/home/hutao/AstraPulse/modules/config.toml",
-        )?;
-        let context: ConfigData = toml::from_str(context.as_str())?;
-        looper::Looper::new(context).enter_looper();
+    /// `replay` 为保存的 dumpsys 输出所在目录，见 [`Sources::replay`]
+    pub fn try_start_scheduler(config: &Path, socket: &Path, replay: Option<&Path>) -> Result<()> {
+        log::info!("使用配置文件{}", config.display());
+        let context = ConfigData::load(config)?;
+        let sources = match replay {
+            Some(dir) => {
+                log::info!("从{}回放顶层应用与屏幕状态", dir.display());
+                Sources::replay(dir)?
+            }
+            None => Sources::dumpsys(),
+        };
+        let (sender, receiver) = mpsc::channel();
+
+        let path = config.to_path_buf();
+        let config_sender = sender.clone();
+        if let Err(e) = watcher::watch(config, move || match ConfigData::load(&path) {
+            Ok(context) => {
+                let _ = config_sender.send(Event::Config(Box::new(context)));
+            }
+            Err(e) => log::error!("配置文件重载失败，继续使用旧配置：{e:#}"),
+        }) {
+            log::error!("无法监听配置文件，热重载不可用：{e}");
+        }
+
+        // 启动前留下的内容可能是很久以前写入的，只响应启动后的修改
+        let mode_file = module_dir().join(scene::MODE_FILE);
+        let path = mode_file.clone();
+        let scene_sender = sender.clone();
+        if let Err(e) = watcher::watch(&mode_file, move || {
+            let _ = scene_sender.send(Event::Scene(scene::read(&path)));
+        }) {
+            log::error!("无法监听 Scene 模式文件{}：{e}", mode_file.display());
+        }
+
+        match control::server::serve(socket, sender) {
+            Ok(()) => log::info!("控制接口监听于{}", socket.display()),
+            Err(e) => log::error!("控制接口不可用：{e:#}"),
+        }
+
+        let status_file = status::StatusFile::new(module_dir().join(status::STATUS_FILE));
+        let journal = journal::Journal::new(module_dir().join(journal::JOURNAL_FILE));
+        looper::Looper::new(
+            config.to_path_buf(),
+            context,
+            sources,
+            receiver,
+            status_file,
+            journal,
+        )
+        .enter_looper();
         Ok(())
     }
 }
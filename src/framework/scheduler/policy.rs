@@ -0,0 +1,65 @@
+// Copyright 2023-2025, [rust@localhost] $ (@3532340532)
+//
+// This file is part of EfficientScheduler.
+//
+// EfficientScheduler is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// EfficientScheduler is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along
+// with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::framework::{ConfigData, Mode, config::mode::Profile};
+
+use super::{
+    dump::window::VisibleWindow,
+    rules::{self, Reason},
+};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SystemState {
+    pub topapp: String,
+    /// 所有可见的应用窗口，用于 `[topapp]` 中的 `aggregate`
+    pub windows: Vec<VisibleWindow>,
+    pub screen_on: bool,
+    /// 通过控制接口设置的手动覆盖
+    pub manual: Option<Mode>,
+    /// Scene 写入的全局覆盖
+    pub scene: Option<Mode>,
+}
+
+#[derive(Clone, Debug)]
+pub struct DesiredState {
+    pub mode: Mode,
+    pub reason: Reason,
+    pub profile: Profile,
+}
+
+/// 根据观测到的系统状态决定应处于的状态，不应产生任何副作用
+pub trait Policy {
+    fn decide(&self, config: &ConfigData, state: &SystemState) -> DesiredState;
+}
+
+/// 把决定好的状态写入内核
+pub trait Actuator {
+    fn apply(&mut self, desired: &DesiredState);
+}
+
+pub struct RulePolicy;
+
+impl Policy for RulePolicy {
+    fn decide(&self, config: &ConfigData, state: &SystemState) -> DesiredState {
+        let decision = rules::evaluate(config, state);
+        DesiredState {
+            profile: config.profile(&decision.mode).clone(),
+            mode: decision.mode,
+            reason: decision.reason,
+        }
+    }
+}
@@ -0,0 +1,499 @@
+// Copyright 2023-2025, [rust@localhost] $ (@3532340532)
+//
+// This file is part of EfficientScheduler.
+//
+// EfficientScheduler is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// EfficientScheduler is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along
+// with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::framework::{
+    ConfigData, Mode,
+    config::{matcher::MatchKind, topapp::Aggregate},
+};
+
+use super::{aggregate, policy::SystemState};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Reason {
+    Manual,
+    Scene,
+    ScreenOff,
+    App {
+        kind: MatchKind,
+        pattern: String,
+    },
+    /// 多个应用可见时按 `aggregate` 选出的应用
+    Visible {
+        package: String,
+        rule: Option<(MatchKind, String)>,
+        aggregate: Aggregate,
+    },
+    Default,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Manual => write!(f, "手动覆盖"),
+            Self::Scene => write!(f, "Scene 全局覆盖"),
+            Self::ScreenOff => write!(f, "息屏"),
+            Self::App { kind, pattern } => write!(f, "命中{kind:?}规则 `{pattern}`"),
+            Self::Visible {
+                package,
+                rule: Some((kind, pattern)),
+                aggregate,
+            } => write!(
+                f,
+                "可见应用 `{package}` 命中{kind:?}规则 `{pattern}`（{aggregate}）"
+            ),
+            Self::Visible {
+                package,
+                rule: None,
+                aggregate,
+            } => write!(f, "可见应用 `{package}` 未命中应用规则（{aggregate}）"),
+            Self::Default => write!(f, "未命中应用规则"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Decision {
+    pub mode: Mode,
+    pub reason: Reason,
+}
+
+/// 按以下顺序求值，命中即返回：
+/// 1. 通过控制接口手动指定了已定义的模式时使用该模式
+/// 2. 息屏时使用 `off`
+/// 3. Scene 指定了已定义的模式时使用该模式
+/// 4. `aggregate` 不为 `top-app` 且有可见的应用窗口时，按其策略在各可见应用对应的模式中选择，
+///    未命中规则的应用对应 `on`
+/// 5. 顶层应用命中 `app` 中的规则，优先级见 [`AppMatcher`](crate::framework::config::matcher::AppMatcher)
+/// 6. 其余情况使用 `on`
+pub fn evaluate(config: &ConfigData, state: &SystemState) -> Decision {
+    if let Some(mode) = &state.manual
+        && config.mode.contains_key(mode)
+    {
+        return Decision {
+            mode: mode.clone(),
+            reason: Reason::Manual,
+        };
+    }
+    if !state.screen_on {
+        return Decision {
+            mode: config.off.clone(),
+            reason: Reason::ScreenOff,
+        };
+    }
+    if let Some(mode) = &state.scene
+        && config.mode.contains_key(mode)
+    {
+        return Decision {
+            mode: mode.clone(),
+            reason: Reason::Scene,
+        };
+    }
+    let aggregate = config.topapp.aggregate;
+    let candidates = aggregate::candidates(config, &state.windows);
+    if let Some(chosen) = aggregate::pick(aggregate, &candidates) {
+        let chosen = &candidates[chosen];
+        return Decision {
+            mode: chosen.mode.clone(),
+            reason: Reason::Visible {
+                package: chosen.package.to_string(),
+                rule: chosen
+                    .found
+                    .as_ref()
+                    .map(|found| (found.kind, found.pattern.to_string())),
+                aggregate,
+            },
+        };
+    }
+    if let Some(found) = config.find_app(&state.topapp) {
+        return Decision {
+            mode: found.mode.clone(),
+            reason: Reason::App {
+                kind: found.kind,
+                pattern: found.pattern.to_string(),
+            },
+        };
+    }
+    Decision {
+        mode: config.on.clone(),
+        reason: Reason::Default,
+    }
+}
+
+/// [`explain`] 中的一步
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Step {
+    pub rule: &'static str,
+    pub detail: String,
+    /// 是否为决定最终模式的一步
+    pub hit: bool,
+}
+
+/// 按与 [`evaluate`] 相同的顺序说明求值过程，到命中的一步为止；
+/// 命中应用规则时，同时列出被覆盖的低优先级规则
+pub fn explain(config: &ConfigData, state: &SystemState) -> Vec<Step> {
+    let mut steps = Vec::new();
+    let mut push = |rule, detail: String, hit| {
+        steps.push(Step { rule, detail, hit });
+        hit
+    };
+
+    let (detail, hit) = overridden(config, state.manual.as_ref());
+    if push("手动覆盖", detail, hit) {
+        return steps;
+    }
+    if !state.screen_on {
+        push("屏幕", format!("息屏，使用 off = {}", config.off), true);
+        return steps;
+    }
+    push("屏幕", String::from("亮屏，跳过"), false);
+    let (detail, hit) = overridden(config, state.scene.as_ref());
+    if push("Scene", detail, hit) {
+        return steps;
+    }
+
+    let aggregate = config.topapp.aggregate;
+    let candidates = aggregate::candidates(config, &state.windows);
+    match aggregate::pick(aggregate, &candidates) {
+        Some(chosen) => {
+            for (index, candidate) in candidates.iter().enumerate() {
+                let rule = candidate.found.as_ref().map_or_else(
+                    || String::from("未命中规则"),
+                    |found| format!("{:?} `{}`", found.kind, found.pattern),
+                );
+                let detail = format!(
+                    "`{}` {rule} -> {}（rank {}，权重 {}）",
+                    candidate.package, candidate.mode, candidate.rank, candidate.weight
+                );
+                push("可见应用", detail, index == chosen);
+            }
+            return steps;
+        }
+        None if aggregate != Aggregate::TopApp => {
+            push(
+                "可见应用",
+                String::from("没有可见的应用窗口，按顶层应用"),
+                false,
+            );
+        }
+        None => {}
+    }
+
+    let mut matched = false;
+    for (index, found) in config.app_matches(&state.topapp).enumerate() {
+        let mut detail = format!("{:?} `{}` -> {}", found.kind, found.pattern, found.mode);
+        if index > 0 {
+            detail.push_str("（优先级较低）");
+        }
+        matched = true;
+        push("应用规则", detail, index == 0);
+    }
+    if matched {
+        return steps;
+    }
+    push(
+        "应用规则",
+        format!("`{}` 未命中任何规则", state.topapp),
+        false,
+    );
+    push("默认", format!("使用 on = {}", config.on), true);
+    steps
+}
+
+/// 手动覆盖与 Scene 的说明，以及是否命中
+fn overridden(config: &ConfigData, mode: Option<&Mode>) -> (String, bool) {
+    match mode {
+        None => (String::from("未设置"), false),
+        Some(mode) if config.mode.contains_key(mode) => (mode.to_string(), true),
+        Some(mode) => (format!("模式 `{mode}` 未定义，跳过"), false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framework::scheduler::dump::window::{VisibleWindow, WindowType, WindowingMode};
+
+    fn decide(config: &ConfigData, topapp: &str) -> Decision {
+        evaluate(
+            config,
+            &SystemState {
+                topapp: topapp.into(),
+                screen_on: true,
+                ..SystemState::default()
+            },
+        )
+    }
+
+    #[test]
+    fn screen_off_wins_over_app_rules() {
+        let config = ConfigData::for_test("\"com.example.game\" = \"fast\"\n");
+        let decision = evaluate(
+            &config,
+            &SystemState {
+                topapp: "com.example.game".into(),
+                screen_on: false,
+                ..SystemState::default()
+            },
+        );
+        assert_eq!(decision.mode, Mode::Powersave);
+        assert_eq!(decision.reason, Reason::ScreenOff);
+    }
+
+    #[test]
+    fn scene_overrides_app_rules_but_not_screen_off() {
+        let config = ConfigData::for_test("\"com.example.game\" = \"fast\"\n");
+        for (screen_on, mode, reason) in [
+            (true, Mode::Performance, Reason::Scene),
+            (false, Mode::Powersave, Reason::ScreenOff),
+        ] {
+            let decision = evaluate(
+                &config,
+                &SystemState {
+                    topapp: "com.example.game".into(),
+                    screen_on,
+                    scene: Some(Mode::Performance),
+                    ..SystemState::default()
+                },
+            );
+            assert_eq!(decision.mode, mode);
+            assert_eq!(decision.reason, reason);
+        }
+    }
+
+    #[test]
+    fn undefined_scene_mode_is_ignored() {
+        let config = ConfigData::for_test("\"com.example.game\" = \"fast\"\n");
+        let decision = evaluate(
+            &config,
+            &SystemState {
+                topapp: "com.example.game".into(),
+                screen_on: true,
+                scene: Some(Mode::Custom("turbo".into())),
+                ..SystemState::default()
+            },
+        );
+        assert_eq!(decision.mode, Mode::Fast);
+    }
+
+    #[test]
+    fn manual_wins_over_scene() {
+        let config = ConfigData::for_test("\"com.example.game\" = \"fast\"\n");
+        let decision = evaluate(
+            &config,
+            &SystemState {
+                topapp: "com.example.game".into(),
+                screen_on: false,
+                manual: Some(Mode::Balance),
+                scene: Some(Mode::Performance),
+                ..SystemState::default()
+            },
+        );
+        assert_eq!(decision.mode, Mode::Balance);
+        assert_eq!(decision.reason, Reason::Manual);
+    }
+
+    #[test]
+    fn unmatched_app_falls_back_to_on() {
+        let config = ConfigData::for_test("\"com.example.game\" = \"fast\"\n");
+        let decision = decide(&config, "com.example.reader");
+        assert_eq!(decision.mode, Mode::Balance);
+        assert_eq!(decision.reason, Reason::Default);
+        assert_eq!(decide(&config, "").reason, Reason::Default);
+    }
+
+    #[test]
+    fn exact_wins_over_glob_and_regex() {
+        let config = ConfigData::for_test(
+            "\"com.tencent.tmgp.sgame\" = \"fast\"\n\
+             \"com.tencent.*\" = \"powersave\"\n\
+             \"^com\\\\.tencent\\\\.\" = \"performance\"\n",
+        );
+        let decision = decide(&config, "com.tencent.tmgp.sgame");
+        assert_eq!(decision.mode, Mode::Fast);
+        assert_eq!(
+            decision.reason,
+            Reason::App {
+                kind: MatchKind::Exact,
+                pattern: "com.tencent.tmgp.sgame".into()
+            }
+        );
+    }
+
+    #[test]
+    fn longest_glob_wins() {
+        let config = ConfigData::for_test(
+            "\"com.tencent.*\" = \"powersave\"\n\
+             \"com.tencent.tmgp.*\" = \"fast\"\n",
+        );
+        assert_eq!(decide(&config, "com.tencent.tmgp.pubgm").mode, Mode::Fast);
+        assert_eq!(decide(&config, "com.tencent.mm").mode, Mode::Powersave);
+    }
+
+    #[test]
+    fn glob_wins_over_regex() {
+        let config = ConfigData::for_test(
+            "\"com.mihoyo.*\" = \"fast\"\n\
+             \"^com\\\\.mihoyo\\\\.\" = \"performance\"\n",
+        );
+        let decision = decide(&config, "com.mihoyo.yuanshen");
+        assert_eq!(decision.mode, Mode::Fast);
+        assert!(matches!(
+            decision.reason,
+            Reason::App {
+                kind: MatchKind::Glob,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn regex_matches_when_nothing_else_does() {
+        let config =
+            ConfigData::for_test("\"^com\\\\.(mihoyo|hoyoverse)\\\\.\" = \"performance\"\n");
+        assert_eq!(
+            decide(&config, "com.hoyoverse.hkrpg").mode,
+            Mode::Performance
+        );
+        assert_eq!(decide(&config, "com.hoyoverse").reason, Reason::Default);
+    }
+
+    #[test]
+    fn glob_must_match_whole_package() {
+        let config = ConfigData::for_test("\"com.tencent.*\" = \"fast\"\n");
+        assert_eq!(decide(&config, "xcom.tencent.mm").reason, Reason::Default);
+        assert_eq!(decide(&config, "com.tencentx").reason, Reason::Default);
+    }
+
+    #[test]
+    fn result_does_not_depend_on_map_order() {
+        let app = "\"com.a.*\" = \"powersave\"\n\
+                   \"com.b.*\" = \"performance\"\n\
+                   \"com.?.game\" = \"fast\"\n\
+                   \"^com\\\\.\" = \"balance\"\n";
+        let expected = decide(&ConfigData::for_test(app), "com.a.game");
+        for _ in 0..32 {
+            assert_eq!(decide(&ConfigData::for_test(app), "com.a.game"), expected);
+        }
+        assert_eq!(expected.mode, Mode::Fast);
+    }
+
+    #[test]
+    fn explain_agrees_with_evaluate() {
+        let config = ConfigData::for_test(
+            "\"com.tencent.*\" = \"fast\"\n\
+             \"^com\\\\.tencent\\\\.\" = \"performance\"\n",
+        );
+        for (topapp, screen_on, manual, scene) in [
+            ("com.tencent.mm", true, None, None),
+            ("com.tencent.mm", false, None, None),
+            ("com.example.reader", true, None, None),
+            ("com.example.reader", true, Some(Mode::Fast), None),
+            ("com.example.reader", false, None, Some(Mode::Fast)),
+            (
+                "com.example.reader",
+                true,
+                None,
+                Some(Mode::Custom("turbo".into())),
+            ),
+        ] {
+            let state = SystemState {
+                topapp: topapp.into(),
+                screen_on,
+                manual,
+                scene,
+                ..SystemState::default()
+            };
+            let steps = explain(&config, &state);
+            let hits: Vec<_> = steps.iter().filter(|step| step.hit).collect();
+            assert_eq!(hits.len(), 1, "{steps:?}");
+            let decision = evaluate(&config, &state);
+            assert!(
+                hits[0].detail.contains(&decision.mode.to_string()),
+                "{steps:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn explain_lists_shadowed_app_rules() {
+        let config = ConfigData::for_test(
+            "\"com.tencent.tmgp.sgame\" = \"fast\"\n\
+             \"com.tencent.*\" = \"powersave\"\n\
+             \"^com\\\\.tencent\\\\.\" = \"performance\"\n",
+        );
+        let state = SystemState {
+            topapp: "com.tencent.tmgp.sgame".into(),
+            screen_on: true,
+            ..SystemState::default()
+        };
+        let apps: Vec<_> = explain(&config, &state)
+            .into_iter()
+            .filter(|step| step.rule == "应用规则")
+            .collect();
+        assert_eq!(apps.len(), 3);
+        assert!(apps[0].hit && apps[0].detail.starts_with("Exact"));
+        assert!(!apps[1].hit && apps[1].detail.starts_with("Glob"));
+        assert!(!apps[2].hit && apps[2].detail.starts_with("Regex"));
+    }
+
+    #[test]
+    fn aggregate_uses_all_visible_apps() {
+        let config =
+            ConfigData::for_test("\"com.tencent.*\" = \"fast\"\n\n[topapp]\naggregate = \"max\"\n");
+        let window = |package: &str, focused| VisibleWindow {
+            package: package.into(),
+            kind: WindowType::Application,
+            mode: WindowingMode::MultiWindow,
+            display: 0,
+            focused,
+        };
+        let state = SystemState {
+            topapp: "com.example.reader".into(),
+            windows: vec![
+                window("com.example.reader", true),
+                window("com.tencent.mm", false),
+            ],
+            screen_on: true,
+            ..SystemState::default()
+        };
+        let decision = evaluate(&config, &state);
+        assert_eq!(decision.mode, Mode::Fast);
+        assert_eq!(
+            decision.reason,
+            Reason::Visible {
+                package: "com.tencent.mm".into(),
+                rule: Some((MatchKind::Glob, "com.tencent.*".into())),
+                aggregate: Aggregate::Max,
+            }
+        );
+        let steps = explain(&config, &state);
+        let hits: Vec<_> = steps.iter().filter(|step| step.hit).collect();
+        assert_eq!(hits.len(), 1, "{steps:?}");
+        assert!(hits[0].detail.starts_with("`com.tencent.mm`"), "{steps:?}");
+
+        // 没有可见窗口时按顶层应用
+        let state = SystemState {
+            windows: Vec::new(),
+            ..state
+        };
+        assert_eq!(evaluate(&config, &state).reason, Reason::Default);
+    }
+}
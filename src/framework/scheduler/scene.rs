@@ -0,0 +1,72 @@
+// Copyright 2023-2025, [rust@localhost] $ (@3532340532)
+//
+// This file is part of EfficientScheduler.
+//
+// EfficientScheduler is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// EfficientScheduler is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along
+// with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{fs, path::Path};
+
+use crate::framework::{ConfigData, Mode};
+
+/// Scene 通过 `powercfg.sh` 把用户选择的模式写入此文件
+pub const MODE_FILE: &str = "mode";
+
+/// 文件不存在、为空或为 `auto` 时表示不覆盖，交由配置文件中的规则决定
+pub fn read(path: &Path) -> Option<Mode> {
+    let context = fs::read_to_string(path).ok()?;
+    match context.trim() {
+        "" | "auto" => None,
+        name => Some(Mode::from(name.to_string())),
+    }
+}
+
+/// 更新 Scene 的全局覆盖，返回是否需要重新决策；
+/// 配置中未定义的模式会被忽略，保留原有的覆盖
+pub fn update(current: &mut Option<Mode>, config: &ConfigData, scene: Option<Mode>) -> bool {
+    if scene == *current {
+        return false;
+    }
+    match &scene {
+        Some(mode) if !config.mode.contains_key(mode) => {
+            log::error!("Scene 指定了未定义的模式 `{mode}`，已忽略");
+            return false;
+        }
+        Some(mode) => log::info!(event = "scene", mode:% = mode; "Scene 全局覆盖为{mode}模式"),
+        None => log::info!(event = "scene_clear"; "已清除 Scene 全局覆盖，交由规则决定"),
+    }
+    *current = scene;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undefined_mode_keeps_previous_override() {
+        let config = ConfigData::for_test("");
+        let mut scene = None;
+
+        assert!(update(&mut scene, &config, Some(Mode::Performance)));
+        assert!(!update(&mut scene, &config, Some(Mode::Performance)));
+        assert!(!update(
+            &mut scene,
+            &config,
+            Some(Mode::Custom("turbo".into()))
+        ));
+        assert_eq!(scene, Some(Mode::Performance));
+        assert!(update(&mut scene, &config, None));
+        assert_eq!(scene, None);
+    }
+}
@@ -0,0 +1,101 @@
+// Copyright 2023-2025, [rust@localhost] $ (@3532340532)
+//
+// This file is part of EfficientScheduler.
+//
+// EfficientScheduler is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// EfficientScheduler is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along
+// with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+    fs::Permissions,
+    io::Write,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use serde_json::Value;
+use tempfile::NamedTempFile;
+
+/// 供模块网页界面与 Scene 面板读取的状态文件，内容与控制接口的 `status` 相同
+pub const STATUS_FILE: &str = "status.json";
+
+pub struct StatusFile {
+    path: PathBuf,
+    last: String,
+    failed: bool,
+}
+
+impl StatusFile {
+    pub const fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            last: String::new(),
+            failed: false,
+        }
+    }
+
+    /// 内容变化时才写入，失败只记录一次
+    pub fn update(&mut self, status: &Value) {
+        let context = status.to_string();
+        if context == self.last {
+            return;
+        }
+        match write_atomic(&self.path, &context) {
+            Ok(()) => {
+                self.last = context;
+                self.failed = false;
+            }
+            Err(e) => {
+                if !self.failed {
+                    log::error!("无法写入状态文件{}：{e:#}", self.path.display());
+                }
+                self.failed = true;
+            }
+        }
+    }
+}
+
+/// 先写入同目录下的临时文件再重命名，读取方不会读到写了一半的内容
+fn write_atomic(path: &Path, context: &str) -> Result<()> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut file = NamedTempFile::new_in(dir)?;
+    file.write_all(context.as_bytes())?;
+    file.as_file()
+        .set_permissions(Permissions::from_mode(0o644))?;
+    file.persist(path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn replaces_file_without_leftovers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(STATUS_FILE);
+        let mut file = StatusFile::new(path.clone());
+
+        file.update(&json!({ "mode": "balance" }));
+        file.update(&json!({ "mode": "fast" }));
+        let status: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(status["mode"], "fast");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o644);
+    }
+}
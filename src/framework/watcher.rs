@@ -0,0 +1,66 @@
+// Copyright 2023-2025, [rust@localhost] $ (@3532340532)
+//
+// This file is part of EfficientScheduler.
+//
+// EfficientScheduler is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// EfficientScheduler is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more
+// details.
+//
+// You should have received a copy of the GNU General Public License along
+// with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{ffi::OsString, path::Path, thread, time::Duration};
+
+use anyhow::{Context, Result};
+use inotify::{Inotify, WatchMask};
+
+/// 监听文件所在目录而非文件本身，这样编辑器以“写临时文件再重命名”方式保存时也能收到通知
+pub fn watch<F>(path: &Path, mut on_change: F) -> Result<()>
+where
+    F: FnMut() + Send + 'static,
+{
+    let dir = path
+        .parent()
+        .with_context(|| format!("无效的路径{}", path.display()))?;
+    // 相对路径 `config.toml` 的父目录为空
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let name: OsString = path
+        .file_name()
+        .with_context(|| format!("无效的路径{}", path.display()))?
+        .to_owned();
+    let inotify = Inotify::init()?;
+    inotify
+        .watches()
+        .add(dir, WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO)?;
+
+    thread::Builder::new()
+        .name("watcher".into())
+        .spawn(move || {
+            let mut inotify = inotify;
+            let mut buffer = [0; 4096];
+            loop {
+                let changed = match inotify.read_events_blocking(&mut buffer) {
+                    Ok(mut events) => events.any(|event| event.name == Some(name.as_os_str())),
+                    Err(e) => {
+                        log::error!("无法读取文件变更事件：{e}，正在重试");
+                        thread::sleep(Duration::from_secs(1));
+                        continue;
+                    }
+                };
+                if changed {
+                    on_change();
+                }
+            }
+        })?;
+    Ok(())
+}
@@ -15,39 +15,305 @@
 // You should have received a copy of the GNU General Public License along
 // with EfficientScheduler. If not, see <https://www.gnu.org/licenses/>.
 
-use std::io;
+use std::{
+    env, io,
+    process::Command,
+    sync::{LazyLock, OnceLock, PoisonError, RwLock},
+    time::Duration,
+};
 
 use anyhow::Result;
-use chrono::FixedOffset;
-use flexi_logger::{DeferredNow, LogSpecification, Logger, Record};
+use chrono::{SecondsFormat, Utc};
+use chrono_tz::Tz;
+use flexi_logger::{
+    Age, Cleanup, Criterion, DeferredNow, Duplicate, FileSpec, LevelFilter, LogSpecification,
+    Logger, LoggerHandle, Naming, Record,
+    filter::{LogLineFilter, LogLineWriter},
+};
+use log::kv::{self, VisitSource};
+use serde_json::{Map, Value, json};
+
+use crate::framework::config::log::{Format, LogConfig, Rotate, Timestamp};
+
+/// 丢弃后文件日志会停止写入，需要一直持有
+static HANDLE: OnceLock<LoggerHandle> = OnceLock::new();
+static RULES: LazyLock<RwLock<Rules>> = LazyLock::new(RwLock::default);
+static CLOCK: OnceLock<Clock> = OnceLock::new();
+
+#[derive(Clone, Copy, Debug)]
+enum Clock {
+    Local(Tz),
+    Monotonic,
+}
+
+/// 按 配置 -> `TZ` -> `persist.sys.timezone` 的顺序选择时区，均无效时使用 UTC，
+/// 同时返回时区的来源
+fn pick_timezone(
+    config: Option<Tz>,
+    env: Option<String>,
+    prop: Option<String>,
+) -> (Tz, &'static str) {
+    if let Some(tz) = config {
+        return (tz, "配置文件");
+    }
+    let parse = |name: Option<String>| name.and_then(|name| name.trim().parse::<Tz>().ok());
+    if let Some(tz) = parse(env) {
+        return (tz, "TZ");
+    }
+    if let Some(tz) = parse(prop) {
+        return (tz, "persist.sys.timezone");
+    }
+    (Tz::UTC, "默认")
+}
+
+fn timezone_prop() -> Option<String> {
+    let output = Command::new("getprop")
+        .arg("persist.sys.timezone")
+        .output()
+        .ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// 开机以来的时间，不含休眠，与 `logcat -v monotonic` 一致
+fn monotonic() -> Duration {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    unsafe {
+        libc::clock_gettime(libc::CLOCK_MONOTONIC, &raw mut time);
+    }
+    Duration::new(time.tv_sec as u64, time.tv_nsec as u32)
+}
+
+/// 各模块的日志级别
+#[derive(Debug)]
+struct Rules {
+    default: LevelFilter,
+    /// 键为 `::a::b::` 形式，按长度从长到短排列，越具体越优先
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            default: LevelFilter::Info,
+            modules: Vec::new(),
+        }
+    }
+}
+
+impl Rules {
+    fn new(config: &LogConfig) -> Self {
+        let mut modules: Vec<_> = config
+            .filters
+            .iter()
+            .map(|(module, level)| (format!("::{}::", module.trim_matches(':')), level.0))
+            .collect();
+        modules.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        Self {
+            default: config.level.0,
+            modules,
+        }
+    }
+
+    /// 规则须与模块路径中完整的几段对应，`looper::cpu` 不会匹配 `looper::cpuset`
+    fn level(&self, module: &str) -> LevelFilter {
+        let module = format!("::{module}::");
+        self.modules
+            .iter()
+            .find(|(rule, _)| module.contains(rule.as_str()))
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    /// 交给 `flexi_logger` 的总级别，低于它的日志不会到达 [`ModuleFilter`]
+    fn max(&self) -> LevelFilter {
+        self.modules
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, Ord::max)
+    }
+}
+
+struct ModuleFilter;
+
+impl LogLineFilter for ModuleFilter {
+    fn write(
+        &self,
+        now: &mut DeferredNow,
+        record: &Record,
+        log_line_writer: &dyn LogLineWriter,
+    ) -> io::Result<()> {
+        let level = RULES
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .level(record.module_path().unwrap_or_default());
+        if record.level() <= level {
+            log_line_writer.write(now, record)?;
+        }
+        Ok(())
+    }
+}
+
+/// 替换日志级别与模块过滤，立即生效
+pub fn set_filters(config: &LogConfig) {
+    let rules = Rules::new(config);
+    let max = rules.max();
+    *RULES.write().unwrap_or_else(PoisonError::into_inner) = rules;
+    if let Some(handle) = HANDLE.get() {
+        handle.set_new_spec(LogSpecification::builder().default(max).build());
+    }
+}
 
 fn log_format(
     write: &mut dyn io::Write,
     now: &mut DeferredNow,
     record: &Record<'_>,
 ) -> anyhow::Result<(), io::Error> {
-    let utc_time = now.now();
-    let time_offset = FixedOffset::east_opt(8 * 3600).unwrap();
-    let time = utc_time.with_timezone(&time_offset);
-    let time_format = time.format("%Y-%m-%d %H:%M:%S");
-    write!(
-        write,
-        "[{time_format}] {}: {}",
-        record.level(),
-        record.args()
-    )
+    match CLOCK.get().copied().unwrap_or(Clock::Local(Tz::UTC)) {
+        Clock::Local(tz) => {
+            let time = now.now().with_timezone(&tz);
+            let time_format = time.format("%Y-%m-%d %H:%M:%S");
+            write!(
+                write,
+                "[{time_format}] {}: {}",
+                record.level(),
+                record.args()
+            )
+        }
+        Clock::Monotonic => {
+            let time = now.now().with_timezone(&Utc);
+            let time_format = time.format("%Y-%m-%d %H:%M:%S%.3f");
+            write!(
+                write,
+                "[{time_format} UTC {:.3}] {}: {}",
+                monotonic().as_secs_f64(),
+                record.level(),
+                record.args()
+            )
+        }
+    }
+}
+
+/// 日志附带的键值对，数值与布尔值保持原类型，其余转为字符串
+#[derive(Default)]
+struct Fields(Map<String, Value>);
+
+impl<'kvs> VisitSource<'kvs> for Fields {
+    fn visit_pair(&mut self, key: kv::Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        let value = value
+            .to_bool()
+            .map(Value::from)
+            .or_else(|| value.to_u64().map(Value::from))
+            .or_else(|| value.to_i64().map(Value::from))
+            .or_else(|| value.to_f64().map(Value::from))
+            .unwrap_or_else(|| Value::from(value.to_string()));
+        self.0.insert(key.to_string(), value);
+        Ok(())
+    }
 }
 
-pub fn log_init() -> Result<()> {
-    let logger_spec = if cfg!(debug_assertions) {
-        LogSpecification::debug()
+/// 每行一个 JSON 对象，`event` 取自同名的键值对，未提供时为 `message`
+fn json_format(
+    write: &mut dyn io::Write,
+    now: &mut DeferredNow,
+    record: &Record<'_>,
+) -> anyhow::Result<(), io::Error> {
+    let mut fields = Fields::default();
+    let _ = record.key_values().visit(&mut fields);
+    let mut fields = fields.0;
+    let event = match fields.remove("event") {
+        Some(Value::String(event)) => event,
+        _ => String::from("message"),
+    };
+    let clock = CLOCK.get().copied().unwrap_or(Clock::Local(Tz::UTC));
+    let time = match clock {
+        Clock::Local(tz) => now
+            .now()
+            .with_timezone(&tz)
+            .to_rfc3339_opts(SecondsFormat::Millis, true),
+        Clock::Monotonic => now
+            .now()
+            .with_timezone(&Utc)
+            .to_rfc3339_opts(SecondsFormat::Millis, true),
+    };
+    let mut line = json!({
+        "time": time,
+        "level": record.level().as_str(),
+        "module": record.module_path(),
+        "event": event,
+        "msg": record.args().to_string(),
+        "fields": fields,
+    });
+    if matches!(clock, Clock::Monotonic) {
+        line["uptime"] = json!(monotonic().as_secs_f64());
+    }
+    serde_json::to_writer(write, &line).map_err(io::Error::from)
+}
+
+/// `stdout` 为真时无论配置如何都输出到标准输出
+pub fn log_init(config: &LogConfig, stdout: bool) -> Result<()> {
+    set_filters(config);
+    let (tz, source) = pick_timezone(
+        config.timezone.map(|timezone| timezone.0),
+        env::var("TZ").ok(),
+        timezone_prop(),
+    );
+    let clock = match config.timestamp {
+        Timestamp::Local => Clock::Local(tz),
+        Timestamp::Monotonic => Clock::Monotonic,
+    };
+    let _ = CLOCK.set(clock);
+    let max = RULES.read().unwrap_or_else(PoisonError::into_inner).max();
+    let logger = || {
+        Logger::with(LogSpecification::builder().default(max).build())
+            .filter(Box::new(ModuleFilter))
+            .format(match config.format {
+                Format::Text => log_format,
+                Format::Json => json_format,
+            })
+    };
+    let handle = if config.file {
+        let size = config.max_size * 1024;
+        let criterion = match config.rotate {
+            Some(Rotate::Day) => Criterion::AgeOrSize(Age::Day, size),
+            Some(Rotate::Hour) => Criterion::AgeOrSize(Age::Hour, size),
+            None => Criterion::Size(size),
+        };
+        let duplicate = if stdout || config.stdout {
+            Duplicate::All
+        } else {
+            Duplicate::None
+        };
+        let file = FileSpec::default()
+            .directory(&config.dir)
+            .basename("EfficientScheduler")
+            .suppress_timestamp();
+        let handle = logger()
+            .log_to_file(file)
+            .rotate(
+                criterion,
+                Naming::Numbers,
+                Cleanup::KeepLogFiles(config.keep),
+            )
+            .append()
+            .duplicate_to_stdout(duplicate)
+            .start();
+        match handle {
+            Ok(handle) => handle,
+            Err(e) => {
+                let handle = logger().log_to_stdout().start()?;
+                log::error!(
+                    "无法写入日志目录{}，改为输出到标准输出：{e}",
+                    config.dir.display()
+                );
+                handle
+            }
+        }
     } else {
-        LogSpecification::info()
+        logger().log_to_stdout().start()?
     };
-    Logger::with(logger_spec)
-        .log_to_stdout()
-        .format(log_format)
-        .start()?;
+    let _ = HANDLE.set(handle);
     log::info!(
         "EfficientScheduler v{} {}, llvm-{}, rustc-{}, build by {} at {} on {},{},{}",
         env!("CARGO_PKG_VERSION"),
@@ -60,6 +326,10 @@ pub fn log_init() -> Result<()> {
         env!("VERGEN_SYSINFO_OS_VERSION"),
         env!("VERGEN_RUSTC_HOST_TRIPLE")
     );
+    match clock {
+        Clock::Local(tz) => log::info!("日志时区 {tz}（来自{source}）"),
+        Clock::Monotonic => log::info!("日志时间为 UTC 与开机以来的秒数"),
+    }
     Ok(())
 }
 
@@ -70,3 +340,102 @@ const fn build_type() -> &'static str {
         "release"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::framework::config::log::Level;
+
+    #[test]
+    fn timezone_sources_in_order() {
+        let shanghai = Some(String::from("Asia/Shanghai"));
+        let berlin = Some(String::from("Europe/Berlin\n"));
+        assert_eq!(
+            pick_timezone(
+                Some(Tz::America__New_York),
+                shanghai.clone(),
+                berlin.clone()
+            ),
+            (Tz::America__New_York, "配置文件")
+        );
+        assert_eq!(
+            pick_timezone(None, shanghai, berlin.clone()),
+            (Tz::Asia__Shanghai, "TZ")
+        );
+        assert_eq!(
+            pick_timezone(None, Some(String::from("CST-8")), berlin),
+            (Tz::Europe__Berlin, "persist.sys.timezone")
+        );
+        assert_eq!(
+            pick_timezone(None, None, Some(String::new())),
+            (Tz::UTC, "默认")
+        );
+    }
+
+    #[test]
+    fn module_rules_match_whole_segments() {
+        let config = LogConfig {
+            level: Level(LevelFilter::Info),
+            filters: BTreeMap::from([
+                ("looper::cpu".into(), Level(LevelFilter::Trace)),
+                ("looper".into(), Level(LevelFilter::Warn)),
+            ]),
+            ..LogConfig::default()
+        };
+        let rules = Rules::new(&config);
+        let looper = "EfficientScheduler::framework::scheduler::looper";
+        assert_eq!(rules.level(&format!("{looper}::cpu")), LevelFilter::Trace);
+        assert_eq!(rules.level(&format!("{looper}::buffer")), LevelFilter::Warn);
+        assert_eq!(rules.level(looper), LevelFilter::Warn);
+        assert_eq!(rules.level(&format!("{looper}::cpuset")), LevelFilter::Warn);
+        assert_eq!(
+            rules.level("EfficientScheduler::framework::kernel"),
+            LevelFilter::Info
+        );
+        assert_eq!(rules.max(), LevelFilter::Trace);
+    }
+
+    #[test]
+    fn json_lines_carry_event_and_fields() {
+        let kvs: [(&str, kv::Value); 3] = [
+            ("event", kv::Value::from("write")),
+            (
+                "path",
+                kv::Value::from("/sys/devices/system/cpu/cpufreq/policy0/scaling_max_freq"),
+            ),
+            ("value", kv::Value::from(1_804_800_u64)),
+        ];
+        let mut buffer = Vec::new();
+        json_format(
+            &mut buffer,
+            &mut DeferredNow::new(),
+            &Record::builder()
+                .args(format_args!("已写入"))
+                .level(log::Level::Trace)
+                .module_path(Some("EfficientScheduler::framework::kernel"))
+                .key_values(&kvs)
+                .build(),
+        )
+        .unwrap();
+        let line: Value = serde_json::from_slice(&buffer).unwrap();
+        assert_eq!(line["level"], "TRACE");
+        assert_eq!(line["module"], "EfficientScheduler::framework::kernel");
+        assert_eq!(line["event"], "write");
+        assert_eq!(line["msg"], "已写入");
+        assert_eq!(line["fields"]["value"], 1_804_800);
+        assert!(line["fields"].get("event").is_none());
+
+        buffer.clear();
+        json_format(
+            &mut buffer,
+            &mut DeferredNow::new(),
+            &Record::builder().args(format_args!("无字段")).build(),
+        )
+        .unwrap();
+        let line: Value = serde_json::from_slice(&buffer).unwrap();
+        assert_eq!(line["event"], "message");
+        assert_eq!(line["fields"], json!({}));
+    }
+}
@@ -28,8 +28,13 @@
 use std::{fs, process::exit};
 
 use anyhow::Result;
+use clap::Parser;
 use sysinfo::{Pid, System};
 
+use cli::{Cli, Command};
+use framework::config::log::LogConfig;
+
+mod cli;
 mod framework;
 mod logger;
 
@@ -41,10 +46,10 @@ fn check_process() {
             if pid.parse::<u32>().is_err() {
                 continue;
             }
-            if let Ok(cmdline) = fs::read_to_string(format!("/proc/{pid}/cmdline")) {
-                if cmdline.contains("EfficientScheduler") {
-                    count += 1;
-                }
+            if let Ok(cmdline) = fs::read_to_string(format!("/proc/{pid}/cmdline"))
+                && cmdline.contains("EfficientScheduler")
+            {
+                count += 1;
             }
         }
     }
@@ -58,46 +63,79 @@ fn kill_other_process() {
     let system = System::new_all();
     for i in system.processes_by_name("uperf".as_ref()) {
         let uperf = format!("{}", i.name().to_string_lossy());
-        if !uperf.is_empty() {
-            if let Some(process) = system.process(Pid::from_u32(i.pid().as_u32())) {
-                process.kill();
-            }
+        if !uperf.is_empty()
+            && let Some(process) = system.process(Pid::from_u32(i.pid().as_u32()))
+        {
+            process.kill();
         }
     }
     for i in system.processes_by_name("fas-rs".as_ref()) {
         let uperf = format!("{}", i.name().to_string_lossy());
-        if !uperf.is_empty() {
-            if let Some(process) = system.process(Pid::from_u32(i.pid().as_u32())) {
-                process.kill();
-            }
+        if !uperf.is_empty()
+            && let Some(process) = system.process(Pid::from_u32(i.pid().as_u32()))
+        {
+            process.kill();
         }
     }
     for i in system.processes_by_name("AsoulOpt".as_ref()) {
         let uperf = format!("{}", i.name().to_string_lossy());
-        if !uperf.is_empty() {
-            if let Some(process) = system.process(Pid::from_u32(i.pid().as_u32())) {
-                process.kill();
-            }
+        if !uperf.is_empty()
+            && let Some(process) = system.process(Pid::from_u32(i.pid().as_u32()))
+        {
+            process.kill();
         }
     }
     for i in system.processes_by_name("AppOpt".as_ref()) {
         let uperf = format!("{}", i.name().to_string_lossy());
-        if !uperf.is_empty() {
-            if let Some(process) = system.process(Pid::from_u32(i.pid().as_u32())) {
-                process.kill();
-            }
+        if !uperf.is_empty()
+            && let Some(process) = system.process(Pid::from_u32(i.pid().as_u32()))
+        {
+            process.kill();
         }
     }
 }
 
 fn main() -> Result<()> {
-    logger::log_init()?;
+    let cli = Cli::parse();
+    // 套接字、配置文件与日志目录的默认位置都在模块目录下，会随之映射
+    if let Some(root) = &cli.root {
+        framework::kernel::set_root(root.clone());
+    }
+    let socket = cli.socket();
+    let restore = matches!(cli.command, Some(Command::Restore));
+    // 客户端命令的输出供脚本使用，不初始化日志
+    if let Some(command) = cli.command
+        && !restore
+    {
+        return cli::run(&socket, command);
+    }
+    let log = framework::config::path::find(cli.config.as_deref())
+        .map(|path| LogConfig::peek(&path))
+        .unwrap_or_default();
+    logger::log_init(&log, cli.stdout || restore)?;
+    if let Some(root) = &cli.root {
+        log::info!("使用模拟根目录{}", root.display());
+    }
+    framework::kernel::set_dry_run(cli.dry_run);
+    if restore {
+        return cli::restore(&socket);
+    }
     check_process();
-    kill_other_process();
-    let _ = fs::write(
-        "/dev/cpuset/background/cgroup.procs",
-        std::process::id().to_string(),
+    if cli.dry_run {
+        log::info!(
+            event = "kill",
+            dry_run = true;
+            "[dry-run] 不结束 uperf、fas-rs、AsoulOpt 与 AppOpt"
+        );
+    } else {
+        kill_other_process();
+    }
+    let _ = framework::kernel::write_untracked(
+        &framework::kernel::path("/dev/cpuset/background/cgroup.procs"),
+        &std::process::id().to_string(),
     );
-    framework::scheduler::Scheduler::try_start_scheduler()?;
+    framework::kernel::snapshot::restore_on_exit()?;
+    let config = framework::config::path::resolve(cli.config)?;
+    framework::scheduler::Scheduler::try_start_scheduler(&config, &socket, cli.replay.as_deref())?;
     Ok(())
 }